@@ -0,0 +1,427 @@
+//! Grid text, a level file format drawing the board with the letters of [`Item::letter`].
+//!
+//! ```text
+//! name: Sample
+//! min_bombs: 2
+//! min_affected_cells: 20
+//!
+//! .  .  .  .  .
+//! .  .  .  R  .
+//! .  S  V  .  .
+//! .  .  .  R  .
+//! M* .  .  J  R
+//! ```
+//!
+//! The header holds one `key: value` per line. `name`, `min_bombs` and `min_affected_cells` are
+//! required, the other fields of [`LevelLayout`] take their default when left out. Enumerations
+//! are written by variant name as in RON files, and the inventory as letters with an optional
+//! count, e.g. `inventory: S x2, H, V x1`.
+//!
+//! The board follows, one row per line with the cells separated by spaces. `.` is an empty cell
+//! and a `*` after a cell lights it when the run starts. Fires are read row by row, so their
+//! order is not kept.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{GridCoord, InventorySlot, Item, LevelLayout, LevelMetaData};
+
+const EMPTY: char = '.';
+const FIRE: char = '*';
+
+/// Why a grid text can't be read. Lines are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridTextError {
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    InvalidValue {
+        line: usize,
+        key: String,
+    },
+    MissingKey(&'static str),
+    InvalidCell {
+        line: usize,
+        cell: String,
+    },
+    /// The row has a different number of cells than the first one.
+    RaggedRow {
+        line: usize,
+    },
+    EmptyBoard,
+    /// The board has more than 255 rows or columns.
+    BoardTooLarge,
+}
+
+impl fmt::Display for GridTextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridTextError::UnknownKey { line, key } => {
+                write!(f, "line {line}: unknown key {key:?}")
+            }
+            GridTextError::InvalidValue { line, key } => {
+                write!(f, "line {line}: invalid value for {key:?}")
+            }
+            GridTextError::MissingKey(key) => write!(f, "missing key {key:?}"),
+            GridTextError::InvalidCell { line, cell } => {
+                write!(f, "line {line}: invalid cell {cell:?}")
+            }
+            GridTextError::RaggedRow { line } => {
+                write!(f, "line {line}: the row is not as wide as the first one")
+            }
+            GridTextError::EmptyBoard => write!(f, "the level has no board"),
+            GridTextError::BoardTooLarge => write!(f, "the board is larger than 255 cells"),
+        }
+    }
+}
+
+impl std::error::Error for GridTextError {}
+
+impl LevelLayout {
+    /// Writes the level as grid text, leaving out the fields that have their default.
+    pub fn to_grid_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("name: {}\n", self.meta.name));
+        out.push_str(&format!("min_bombs: {}\n", self.meta.min_bombs));
+        out.push_str(&format!(
+            "min_affected_cells: {}\n",
+            self.meta.min_affected_cells
+        ));
+        if let Some(inventory) = &self.inventory {
+            let slots: Vec<_> = inventory
+                .iter()
+                .map(|slot| match slot.count {
+                    Some(count) => format!("{} x{count}", slot.item.letter()),
+                    None => slot.item.letter().to_string(),
+                })
+                .collect();
+            out.push_str(&format!("inventory: {}\n", slots.join(", ")));
+        }
+        if !self.enemy_behavior.is_idle() {
+            out.push_str(&format!("enemy_behavior: {:?}\n", self.enemy_behavior));
+        }
+        if !self.voids.passes_blasts() {
+            out.push_str(&format!("voids: {:?}\n", self.voids));
+        }
+        if self.fuse_ticks != Self::DEFAULT_FUSE_TICKS {
+            out.push_str(&format!("fuse_ticks: {}\n", self.fuse_ticks));
+        }
+        if self.hard_rock_hits != Self::DEFAULT_HARD_ROCK_HITS {
+            out.push_str(&format!("hard_rock_hits: {}\n", self.hard_rock_hits));
+        }
+        if !self.topology.is_bounded() {
+            out.push_str(&format!("topology: {:?}\n", self.topology));
+        }
+
+        out.push('\n');
+        let (width, height) = self.board_size;
+        for y in 0..height {
+            let row: Vec<_> = (0..width)
+                .map(|x| {
+                    let coord = GridCoord { x, y };
+                    let letter = self.objects.get(&coord).map_or(EMPTY, |item| item.letter());
+                    let fire = if self.fire_coords.contains(&coord) {
+                        FIRE
+                    } else {
+                        ' '
+                    };
+                    format!("{letter}{fire}")
+                })
+                .collect();
+            out.push_str(row.join(" ").trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Reads a level written as grid text.
+    pub fn from_grid_text(text: &str) -> Result<Self, GridTextError> {
+        let mut name = None;
+        let mut min_bombs = None;
+        let mut min_affected_cells = None;
+        let mut layout = LevelLayout {
+            board_size: (0, 0),
+            objects: HashMap::new(),
+            fire_coords: Vec::new(),
+            inventory: None,
+            enemy_behavior: Default::default(),
+            voids: Default::default(),
+            fuse_ticks: Self::DEFAULT_FUSE_TICKS,
+            hard_rock_hits: Self::DEFAULT_HARD_ROCK_HITS,
+            topology: Default::default(),
+            meta: LevelMetaData {
+                name: String::new(),
+                min_bombs: 0,
+                min_affected_cells: 0,
+            },
+        };
+
+        let mut rows = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // the header ends with the first row of the board, which has no `key:`
+            let header_entry = line.split_once(':').filter(|(key, _)| {
+                !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+            let Some((key, value)) = header_entry.filter(|_| rows.is_empty()) else {
+                rows.push((line_number, line));
+                continue;
+            };
+
+            let value = value.trim();
+            let invalid_value = || GridTextError::InvalidValue {
+                line: line_number,
+                key: key.to_string(),
+            };
+            match key {
+                "name" => name = Some(value.to_string()),
+                "min_bombs" => min_bombs = Some(value.parse().map_err(|_| invalid_value())?),
+                "min_affected_cells" => {
+                    min_affected_cells = Some(value.parse().map_err(|_| invalid_value())?);
+                }
+                "inventory" => {
+                    layout.inventory = Some(parse_inventory(value).ok_or_else(invalid_value)?);
+                }
+                "enemy_behavior" => {
+                    layout.enemy_behavior = ron::from_str(value).map_err(|_| invalid_value())?;
+                }
+                "voids" => layout.voids = ron::from_str(value).map_err(|_| invalid_value())?,
+                "fuse_ticks" => layout.fuse_ticks = value.parse().map_err(|_| invalid_value())?,
+                "hard_rock_hits" => {
+                    layout.hard_rock_hits = value.parse().map_err(|_| invalid_value())?;
+                }
+                "topology" => {
+                    layout.topology = ron::from_str(value).map_err(|_| invalid_value())?
+                }
+                _ => {
+                    return Err(GridTextError::UnknownKey {
+                        line: line_number,
+                        key: key.to_string(),
+                    });
+                }
+            }
+        }
+
+        layout.meta = LevelMetaData {
+            name: name.ok_or(GridTextError::MissingKey("name"))?,
+            min_bombs: min_bombs.ok_or(GridTextError::MissingKey("min_bombs"))?,
+            min_affected_cells: min_affected_cells
+                .ok_or(GridTextError::MissingKey("min_affected_cells"))?,
+        };
+
+        let Some(&(_, first_row)) = rows.first() else {
+            return Err(GridTextError::EmptyBoard);
+        };
+        let width = first_row.split_whitespace().count();
+        let width = u8::try_from(width).map_err(|_| GridTextError::BoardTooLarge)?;
+        let height = u8::try_from(rows.len()).map_err(|_| GridTextError::BoardTooLarge)?;
+        layout.board_size = (width, height);
+
+        for (y, (line_number, row)) in (0..height).zip(rows) {
+            let cells: Vec<_> = row.split_whitespace().collect();
+            if cells.len() != width as usize {
+                return Err(GridTextError::RaggedRow { line: line_number });
+            }
+            for (x, cell) in (0..width).zip(cells) {
+                let coord = GridCoord { x, y };
+                let invalid_cell = || GridTextError::InvalidCell {
+                    line: line_number,
+                    cell: cell.to_string(),
+                };
+                let mut chars = cell.chars();
+                let letter = chars.next().ok_or_else(invalid_cell)?;
+                match chars.as_str() {
+                    "" => {}
+                    "*" => layout.fire_coords.push(coord),
+                    _ => return Err(invalid_cell()),
+                }
+                if letter != EMPTY {
+                    let item = Item::from_letter(letter).ok_or_else(invalid_cell)?;
+                    layout.objects.insert(coord, item);
+                }
+            }
+        }
+
+        Ok(layout)
+    }
+}
+
+/// Reads inventory slots like `S x2, H`, `None` if one of them is invalid.
+fn parse_inventory(value: &str) -> Option<Vec<InventorySlot>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|slot| !slot.is_empty())
+        .map(|slot| {
+            let mut chars = slot.chars();
+            let item = Item::from_letter(chars.next()?)?;
+            let count = match chars.as_str().trim() {
+                "" => None,
+                count => Some(count.strip_prefix('x')?.parse().ok()?),
+            };
+            Some(InventorySlot { item, count })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnemyBehavior, Facing, Topology, VoidBehavior};
+
+    /// Fires are read row by row, so the order they were listed in is not compared.
+    fn sort_fires(mut level: LevelLayout) -> LevelLayout {
+        level.fire_coords.sort_by_key(|coord| (coord.y, coord.x));
+        level
+    }
+
+    #[test]
+    fn shipped_levels_round_trip() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/levels");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "ron")
+                || path.ends_with("manifest.ron")
+            {
+                continue;
+            }
+            let level: LevelLayout = ron::de::from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+            let text = level.to_grid_text();
+            let read = LevelLayout::from_grid_text(&text).unwrap();
+            assert_eq!(sort_fires(read), sort_fires(level), "{text}");
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn reads_header_and_board() {
+        let level = LevelLayout::from_grid_text(
+            "name: Every Field
+            min_bombs: 2
+            min_affected_cells: 7
+            inventory: S x2, < , T
+            enemy_behavior: ChaseJewel
+            voids: AbsorbBlasts
+            fuse_ticks: 4
+            hard_rock_hits: 3
+            topology: Torus
+
+            S* .  O
+            X  #  J*",
+        )
+        .unwrap();
+
+        assert_eq!(level.meta.name, "Every Field");
+        assert_eq!(level.meta.min_bombs, 2);
+        assert_eq!(level.meta.min_affected_cells, 7);
+        assert_eq!(
+            level.inventory,
+            Some(vec![
+                InventorySlot {
+                    item: Item::BombSmall,
+                    count: Some(2),
+                },
+                InventorySlot {
+                    item: Item::BombCone(Facing::Left),
+                    count: None,
+                },
+                InventorySlot {
+                    item: Item::BombTimed,
+                    count: None,
+                },
+            ])
+        );
+        assert_eq!(level.enemy_behavior, EnemyBehavior::ChaseJewel);
+        assert_eq!(level.voids, VoidBehavior::AbsorbBlasts);
+        assert_eq!(level.fuse_ticks, 4);
+        assert_eq!(level.hard_rock_hits, 3);
+        assert_eq!(level.topology, Topology::Torus);
+
+        assert_eq!(level.board_size, (3, 2));
+        assert_eq!(
+            level.objects,
+            HashMap::from([
+                (GridCoord { x: 0, y: 0 }, Item::BombSmall),
+                (GridCoord { x: 2, y: 0 }, Item::HardRock),
+                (GridCoord { x: 0, y: 1 }, Item::Enemy),
+                (GridCoord { x: 1, y: 1 }, Item::Wall),
+                (GridCoord { x: 2, y: 1 }, Item::Jewel),
+            ])
+        );
+        assert_eq!(
+            level.fire_coords,
+            [GridCoord { x: 0, y: 0 }, GridCoord { x: 2, y: 1 }]
+        );
+    }
+
+    #[test]
+    fn left_out_fields_take_their_default() {
+        let level =
+            LevelLayout::from_grid_text("name: Plain\nmin_bombs: 0\nmin_affected_cells: 0\n\n. R")
+                .unwrap();
+        assert_eq!(level.inventory, None);
+        assert!(level.enemy_behavior.is_idle());
+        assert!(level.voids.passes_blasts());
+        assert_eq!(level.fuse_ticks, LevelLayout::DEFAULT_FUSE_TICKS);
+        assert_eq!(level.hard_rock_hits, LevelLayout::DEFAULT_HARD_ROCK_HITS);
+        assert!(level.topology.is_bounded());
+    }
+
+    #[test]
+    fn header_errors() {
+        assert_eq!(
+            LevelLayout::from_grid_text("name: A\nmin_bombs: 0\n\n. ."),
+            Err(GridTextError::MissingKey("min_affected_cells"))
+        );
+        assert_eq!(
+            LevelLayout::from_grid_text("name: A\nmin_bombs: many\nmin_affected_cells: 0\n\n."),
+            Err(GridTextError::InvalidValue {
+                line: 2,
+                key: "min_bombs".to_string(),
+            })
+        );
+        assert_eq!(
+            LevelLayout::from_grid_text("name: A\nsize: 3\n\n."),
+            Err(GridTextError::UnknownKey {
+                line: 2,
+                key: "size".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_letter_is_an_invalid_cell() {
+        assert_eq!(
+            LevelLayout::from_grid_text("name: A\nmin_bombs: 0\nmin_affected_cells: 0\n\n. Q ."),
+            Err(GridTextError::InvalidCell {
+                line: 5,
+                cell: "Q".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn ragged_row() {
+        assert_eq!(
+            LevelLayout::from_grid_text(
+                "name: A\nmin_bombs: 0\nmin_affected_cells: 0\n\n. . .\n. .\n. . ."
+            ),
+            Err(GridTextError::RaggedRow { line: 6 })
+        );
+    }
+
+    #[test]
+    fn missing_board() {
+        assert_eq!(
+            LevelLayout::from_grid_text("name: A\nmin_bombs: 0\nmin_affected_cells: 0\n"),
+            Err(GridTextError::EmptyBoard)
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "bevy",
+    derive(bevy::prelude::Component, bevy::prelude::Reflect)
+)]
+pub enum Item {
+    BombSmall,
+    BombMedium,
+    BombLarge,
+    BombHorizontal,
+    BombVertical,
+    Null,
+    Rock,
+    Jewel,
+    Eraser,
+    Enemy,
+    /// Stops line blasts and survives every explosion.
+    Wall,
+    /// A small bomb exploding the level's `fuse_ticks` ticks after it is ignited.
+    BombTimed,
+    /// A rock destroyed by the level's `hard_rock_hits` blasts, counted over all ticks.
+    HardRock,
+    /// A bomb blasting a widening cone in the direction it faces.
+    BombCone(Facing),
+    /// Bends line blasts passing through it by a quarter turn and survives every explosion.
+    Mirror(MirrorTilt),
+}
+
+/// Which diagonal a mirror lies along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum MirrorTilt {
+    /// `/`, sending blasts travelling right upwards.
+    Rising,
+    /// `\`, sending blasts travelling right downwards.
+    Falling,
+}
+
+impl MirrorTilt {
+    /// The direction a blast travelling along `(dx, dy)` leaves the mirror in.
+    pub fn deflect(self, (dx, dy): (i8, i8)) -> (i8, i8) {
+        match self {
+            MirrorTilt::Rising => (dy, dx),
+            MirrorTilt::Falling => (-dy, -dx),
+        }
+    }
+}
+
+/// The direction a directional bomb blasts towards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum Facing {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::Up, Facing::Right, Facing::Down, Facing::Left];
+
+    pub fn clockwise(self) -> Self {
+        match self {
+            Facing::Up => Facing::Right,
+            Facing::Right => Facing::Down,
+            Facing::Down => Facing::Left,
+            Facing::Left => Facing::Up,
+        }
+    }
+
+    pub fn counter_clockwise(self) -> Self {
+        match self {
+            Facing::Up => Facing::Left,
+            Facing::Right => Facing::Up,
+            Facing::Down => Facing::Right,
+            Facing::Left => Facing::Down,
+        }
+    }
+
+    /// Quarter turns clockwise from `Up`.
+    pub fn quarter_turns(self) -> u8 {
+        match self {
+            Facing::Up => 0,
+            Facing::Right => 1,
+            Facing::Down => 2,
+            Facing::Left => 3,
+        }
+    }
+}
+
+impl Item {
+    pub fn is_bomb(&self) -> bool {
+        matches!(
+            self,
+            Item::BombSmall
+                | Item::BombMedium
+                | Item::BombLarge
+                | Item::BombHorizontal
+                | Item::BombVertical
+                | Item::BombTimed
+                | Item::BombCone(_)
+        )
+    }
+
+    /// Whether both items are the same kind of item, whatever way they face.
+    pub fn same_kind(&self, other: &Item) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn facing(&self) -> Option<Facing> {
+        match self {
+            Item::BombCone(facing) => Some(*facing),
+            _ => None,
+        }
+    }
+
+    /// The same item facing `facing`, or unchanged if it has no facing.
+    pub fn facing_towards(self, facing: Facing) -> Self {
+        match self {
+            Item::BombCone(_) => Item::BombCone(facing),
+            item => item,
+        }
+    }
+
+    /// The same item turned a quarter clockwise, or unchanged if it has no facing.
+    pub fn rotated_clockwise(self) -> Self {
+        match self {
+            Item::BombCone(facing) => Item::BombCone(facing.clockwise()),
+            item => item,
+        }
+    }
+
+    /// The same item turned a quarter counter-clockwise, or unchanged if it has no facing.
+    pub fn rotated_counter_clockwise(self) -> Self {
+        match self {
+            Item::BombCone(facing) => Item::BombCone(facing.counter_clockwise()),
+            item => item,
+        }
+    }
+
+    /// Bombs whose blast travels along a line, is stopped by walls and bent by mirrors.
+    pub fn is_line_bomb(&self) -> bool {
+        matches!(self, Item::BombHorizontal | Item::BombVertical)
+    }
+
+    /// Objects that no blast destroys.
+    pub fn is_indestructible(&self) -> bool {
+        matches!(self, Item::Wall | Item::Mirror(_))
+    }
+
+    pub const fn to_sprite_index(self) -> usize {
+        match self {
+            Item::BombSmall => 0,
+            Item::BombMedium => 1,
+            Item::BombLarge => 2,
+            Item::BombHorizontal => 3,
+            Item::BombVertical => 4,
+            Item::Null => 7,
+            Item::Rock => 8,
+            Item::Jewel => 10,
+            Item::Enemy => 21,
+            Item::Eraser => 12,
+            Item::Wall => 13,
+            Item::BombTimed => 14,
+            Item::HardRock => 15,
+            Item::BombCone(_) => 18,
+            Item::Mirror(MirrorTilt::Rising) => 19,
+            Item::Mirror(MirrorTilt::Falling) => 20,
+        }
+    }
+
+    /// The letter standing for the item on boards written as text.
+    pub const fn letter(self) -> char {
+        match self {
+            Item::BombSmall => 'S',
+            Item::BombMedium => 'M',
+            Item::BombLarge => 'L',
+            Item::BombHorizontal => 'H',
+            Item::BombVertical => 'V',
+            Item::Null => 'x',
+            Item::Rock => 'R',
+            Item::Jewel => 'J',
+            Item::Eraser => 'E',
+            Item::Enemy => 'X',
+            Item::Wall => '#',
+            Item::BombTimed => 'T',
+            Item::HardRock => 'O',
+            Item::BombCone(Facing::Up) => '^',
+            Item::BombCone(Facing::Right) => '>',
+            Item::BombCone(Facing::Down) => 'v',
+            Item::BombCone(Facing::Left) => '<',
+            Item::Mirror(MirrorTilt::Rising) => '/',
+            Item::Mirror(MirrorTilt::Falling) => '\\',
+        }
+    }
+
+    /// The item written as `letter`, the reverse of [`Item::letter`].
+    pub const fn from_letter(letter: char) -> Option<Self> {
+        let item = match letter {
+            'S' => Item::BombSmall,
+            'M' => Item::BombMedium,
+            'L' => Item::BombLarge,
+            'H' => Item::BombHorizontal,
+            'V' => Item::BombVertical,
+            'x' => Item::Null,
+            'R' => Item::Rock,
+            'J' => Item::Jewel,
+            'E' => Item::Eraser,
+            'X' => Item::Enemy,
+            '#' => Item::Wall,
+            'T' => Item::BombTimed,
+            'O' => Item::HardRock,
+            '^' => Item::BombCone(Facing::Up),
+            '>' => Item::BombCone(Facing::Right),
+            'v' => Item::BombCone(Facing::Down),
+            '<' => Item::BombCone(Facing::Left),
+            '/' => Item::Mirror(MirrorTilt::Rising),
+            '\\' => Item::Mirror(MirrorTilt::Falling),
+            _ => return None,
+        };
+        Some(item)
+    }
+}
+
+impl Item {
+    pub fn impact_zone(&self) -> &'static [(i8, i8)] {
+        match self {
+            // . . . . .
+            // . x x x .
+            // . x # x .
+            // . x x x.
+            // . . . . .
+            Item::BombSmall | Item::BombTimed => &[
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (-1, 0),
+                (0, 0),
+                (1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ],
+
+            // . . x . .
+            // . x x x .
+            // x x # x x
+            // . x x x .
+            // . . x . .
+            Item::BombMedium => &[
+                (0, 2),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (-2, 0),
+                (-1, 0),
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (0, -2),
+            ],
+
+            // . . . x . . .
+            // . . x x x . .
+            // . x x x x x .
+            // x x x # x x x
+            // . x x x x x .
+            // . . x x x . .
+            // . . . x . . .
+            Item::BombLarge => &[
+                (0, 3),
+                (-1, 2),
+                (0, 2),
+                (1, 2),
+                (-2, 1),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (2, 1),
+                (-3, 0),
+                (-2, 0),
+                (-1, 0),
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (-2, -1),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (2, -1),
+                (-1, -2),
+                (0, -2),
+                (1, -2),
+                (0, -3),
+            ],
+
+            // . . x . .
+            // . . x . .
+            // . . # . .
+            // . . x . .
+            // . . x . .
+            Item::BombVertical => &[
+                (0, 10),
+                (0, 9),
+                (0, 8),
+                (0, 7),
+                (0, 6),
+                (0, 5),
+                (0, 4),
+                (0, 3),
+                (0, 2),
+                (0, 1),
+                (0, 0),
+                (0, -1),
+                (0, -2),
+                (0, -3),
+                (0, -4),
+                (0, -5),
+                (0, -6),
+                (0, -7),
+                (0, -8),
+                (0, -9),
+                (0, -10),
+            ],
+
+            // . . . . .
+            // . . . . .
+            // x x # x x
+            // . . . . .
+            // . . . . .
+            Item::BombHorizontal => &[
+                (10, 0),
+                (9, 0),
+                (8, 0),
+                (7, 0),
+                (6, 0),
+                (5, 0),
+                (4, 0),
+                (3, 0),
+                (2, 0),
+                (1, 0),
+                (0, 0),
+                (-1, 0),
+                (-2, 0),
+                (-3, 0),
+                (-4, 0),
+                (-5, 0),
+                (-6, 0),
+                (-7, 0),
+                (-8, 0),
+                (-9, 0),
+                (-10, 0),
+            ],
+
+            // . . . . . . .
+            // x x x x x x x
+            // . x x x x x .
+            // . . x x x . .
+            // . . . x . . .
+            // . . . # . . .
+            // . . . . . . .
+            Item::BombCone(Facing::Up) => &[
+                (-3, 4),
+                (-2, 4),
+                (-1, 4),
+                (0, 4),
+                (1, 4),
+                (2, 4),
+                (3, 4),
+                (-2, 3),
+                (-1, 3),
+                (0, 3),
+                (1, 3),
+                (2, 3),
+                (-1, 2),
+                (0, 2),
+                (1, 2),
+                (0, 1),
+                (0, 0),
+            ],
+            Item::BombCone(Facing::Right) => &[
+                (4, 3),
+                (4, 2),
+                (4, 1),
+                (4, 0),
+                (4, -1),
+                (4, -2),
+                (4, -3),
+                (3, 2),
+                (3, 1),
+                (3, 0),
+                (3, -1),
+                (3, -2),
+                (2, 1),
+                (2, 0),
+                (2, -1),
+                (1, 0),
+                (0, 0),
+            ],
+            Item::BombCone(Facing::Down) => &[
+                (3, -4),
+                (2, -4),
+                (1, -4),
+                (0, -4),
+                (-1, -4),
+                (-2, -4),
+                (-3, -4),
+                (2, -3),
+                (1, -3),
+                (0, -3),
+                (-1, -3),
+                (-2, -3),
+                (1, -2),
+                (0, -2),
+                (-1, -2),
+                (0, -1),
+                (0, 0),
+            ],
+            Item::BombCone(Facing::Left) => &[
+                (-4, -3),
+                (-4, -2),
+                (-4, -1),
+                (-4, 0),
+                (-4, 1),
+                (-4, 2),
+                (-4, 3),
+                (-3, -2),
+                (-3, -1),
+                (-3, 0),
+                (-3, 1),
+                (-3, 2),
+                (-2, -1),
+                (-2, 0),
+                (-2, 1),
+                (-1, 0),
+                (0, 0),
+            ],
+
+            Item::Eraser => &[(0, 0)],
+
+            Item::Rock
+            | Item::HardRock
+            | Item::Jewel
+            | Item::Enemy
+            | Item::Null
+            | Item::Wall
+            | Item::Mirror(_) => &[],
+        }
+    }
+}
+
+impl From<u8> for Item {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Item::BombSmall,
+            1 => Item::BombMedium,
+            2 => Item::BombLarge,
+            3 => Item::BombHorizontal,
+            4 => Item::BombVertical,
+            255 => Item::Eraser,
+            _ => panic!("Invalid item index"),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::Item;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "bevy",
+    derive(bevy::prelude::Component, bevy::prelude::Reflect)
+)]
+pub struct GridCoord {
+    pub x: u8,
+    pub y: u8,
+}
+
+/// Formats the coordinate the way level files write it, e.g. `(x:2, y:3)`.
+impl fmt::Display for GridCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(x:{}, y:{})", self.x, self.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::asset::Asset, bevy::prelude::Reflect))]
+pub struct LevelLayout {
+    pub board_size: (u8, u8),
+    pub objects: HashMap<GridCoord, Item>,
+    /// Bombs lit when the run starts, each starting a chain of its own. Level files with a
+    /// single fire can write `fire_coord: (x:2, y:2)` instead.
+    #[serde(alias = "fire_coord", deserialize_with = "one_or_many")]
+    pub fire_coords: Vec<GridCoord>,
+    /// The bombs the player may place, every bomb type without limit when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inventory: Option<Vec<InventorySlot>>,
+    #[serde(default, skip_serializing_if = "EnemyBehavior::is_idle")]
+    pub enemy_behavior: EnemyBehavior,
+    /// How `Null` objects, the void cells cut out of the board, treat blasts.
+    #[serde(default, skip_serializing_if = "VoidBehavior::passes_blasts")]
+    pub voids: VoidBehavior,
+    /// Ticks between the ignition and the explosion of a `BombTimed`.
+    #[serde(default = "LevelLayout::default_fuse_ticks")]
+    pub fuse_ticks: u8,
+    /// Blasts needed to destroy a `HardRock`.
+    #[serde(default = "LevelLayout::default_hard_rock_hits")]
+    pub hard_rock_hits: u8,
+    /// What happens to blasts and enemies leaving the board.
+    #[serde(default, skip_serializing_if = "Topology::is_bounded")]
+    pub topology: Topology,
+    pub meta: LevelMetaData,
+}
+
+/// What the enemies of a level do between the ticks of the chain reaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum EnemyBehavior {
+    /// Enemies stay where they are.
+    #[default]
+    Idle,
+    /// Every enemy steps one cell toward the nearest jewel after each tick. A jewel an enemy
+    /// reaches is lost.
+    ChaseJewel,
+}
+
+impl EnemyBehavior {
+    pub fn is_idle(&self) -> bool {
+        *self == EnemyBehavior::Idle
+    }
+}
+
+/// Whether blasts reach the cells behind a void cell. Void cells themselves are never hit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum VoidBehavior {
+    /// Blasts jump over void cells.
+    #[default]
+    PassBlasts,
+    /// Void cells stop blasts, shielding the cells behind them from the bomb.
+    AbsorbBlasts,
+}
+
+impl VoidBehavior {
+    pub fn passes_blasts(&self) -> bool {
+        *self == VoidBehavior::PassBlasts
+    }
+}
+
+/// How the edges of the board connect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum Topology {
+    /// Blasts end at the edges of the board.
+    #[default]
+    Bounded,
+    /// Each edge leads to the opposite one, so blasts leaving the board come back on the other
+    /// side and enemies may take a shortcut across it.
+    Torus,
+}
+
+impl Topology {
+    pub fn is_bounded(&self) -> bool {
+        *self == Topology::Bounded
+    }
+}
+
+/// A bomb type the player may place, and how many of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub struct InventorySlot {
+    pub item: Item,
+    /// Unlimited when `None`.
+    #[serde(default)]
+    pub count: Option<u8>,
+}
+
+impl InventorySlot {
+    const fn unlimited(item: Item) -> Self {
+        Self { item, count: None }
+    }
+}
+
+impl LevelLayout {
+    /// The inventory of levels that don't declare one. `BombLarge` has to be listed explicitly.
+    pub const DEFAULT_INVENTORY: [InventorySlot; 4] = [
+        InventorySlot::unlimited(Item::BombSmall),
+        InventorySlot::unlimited(Item::BombMedium),
+        InventorySlot::unlimited(Item::BombHorizontal),
+        InventorySlot::unlimited(Item::BombVertical),
+    ];
+
+    pub const DEFAULT_FUSE_TICKS: u8 = 3;
+
+    fn default_fuse_ticks() -> u8 {
+        Self::DEFAULT_FUSE_TICKS
+    }
+
+    pub const DEFAULT_HARD_ROCK_HITS: u8 = 2;
+
+    fn default_hard_rock_hits() -> u8 {
+        Self::DEFAULT_HARD_ROCK_HITS
+    }
+
+    /// Every cell of the board, column by column.
+    pub fn cells(&self) -> impl Iterator<Item = GridCoord> + use<> {
+        let (width, height) = self.board_size;
+        (0..width).flat_map(move |x| (0..height).map(move |y| GridCoord { x, y }))
+    }
+
+    /// The bombs the player may place, in the order the item buttons show them.
+    pub fn inventory(&self) -> &[InventorySlot] {
+        self.inventory
+            .as_deref()
+            .unwrap_or(&Self::DEFAULT_INVENTORY)
+    }
+
+    /// How many bombs of `item` the player may place, `None` when unlimited.
+    ///
+    /// Directional bombs share one limit whichever way they face.
+    pub fn item_limit(&self, item: Item) -> Option<u8> {
+        self.inventory()
+            .iter()
+            .find(|slot| slot.item.same_kind(&item))
+            .map_or(Some(0), |slot| slot.count)
+    }
+
+    /// Serializes the level in the layout of the hand-written level files, with one object per
+    /// line sorted row by row so that regenerated files diff cleanly.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        use fmt::Write;
+
+        // destructured so that a new field can't be forgotten here
+        let LevelLayout {
+            board_size: (width, height),
+            objects,
+            fire_coords,
+            inventory,
+            enemy_behavior,
+            voids,
+            fuse_ticks,
+            hard_rock_hits,
+            topology,
+            meta:
+                LevelMetaData {
+                    name,
+                    min_bombs,
+                    min_affected_cells,
+                },
+        } = self;
+        let mut out = String::new();
+        writeln!(out, "(")?;
+        writeln!(out, "    board_size: ({width}, {height}),")?;
+        writeln!(out, "    objects: {{")?;
+        let mut objects: Vec<_> = objects.iter().collect();
+        objects.sort_by_key(|(coord, _)| (coord.y, coord.x));
+        for (coord, item) in objects {
+            writeln!(out, "        {coord}: {},", ron::to_string(item)?)?;
+        }
+        writeln!(out, "    }},")?;
+        match fire_coords.as_slice() {
+            [fire_coord] => writeln!(out, "    fire_coord: {fire_coord},")?,
+            fire_coords => {
+                writeln!(out, "    fire_coords: [")?;
+                for fire_coord in fire_coords {
+                    writeln!(out, "        {fire_coord},")?;
+                }
+                writeln!(out, "    ],")?;
+            }
+        }
+        if let Some(inventory) = inventory {
+            writeln!(out, "    inventory: Some([")?;
+            for slot in inventory {
+                let item = ron::to_string(&slot.item)?;
+                match slot.count {
+                    Some(count) => writeln!(out, "        (item: {item}, count: Some({count})),")?,
+                    None => writeln!(out, "        (item: {item}),")?,
+                }
+            }
+            writeln!(out, "    ]),")?;
+        }
+        if !enemy_behavior.is_idle() {
+            writeln!(
+                out,
+                "    enemy_behavior: {},",
+                ron::to_string(enemy_behavior)?
+            )?;
+        }
+        if !voids.passes_blasts() {
+            writeln!(out, "    voids: {},", ron::to_string(voids)?)?;
+        }
+        if *fuse_ticks != Self::DEFAULT_FUSE_TICKS {
+            writeln!(out, "    fuse_ticks: {},", fuse_ticks)?;
+        }
+        if *hard_rock_hits != Self::DEFAULT_HARD_ROCK_HITS {
+            writeln!(out, "    hard_rock_hits: {},", hard_rock_hits)?;
+        }
+        if !topology.is_bounded() {
+            writeln!(out, "    topology: {},", ron::to_string(topology)?)?;
+        }
+        writeln!(out, "    meta: (")?;
+        writeln!(out, "        name: {},", ron::to_string(name)?)?;
+        writeln!(out, "        min_bombs: {},", min_bombs)?;
+        writeln!(out, "        min_affected_cells: {},", min_affected_cells)?;
+        writeln!(out, "    )")?;
+        writeln!(out, ")")?;
+        Ok(out)
+    }
+}
+
+/// Accepts either a single coordinate or a list of them.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GridCoord>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(GridCoord),
+        Many(Vec<GridCoord>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(coord) => vec![coord],
+        OneOrMany::Many(coords) => coords,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub struct LevelMetaData {
+    pub name: String,
+    pub min_bombs: u8,
+    pub min_affected_cells: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_round_trip(level: &LevelLayout) {
+        let ron = level.to_ron().unwrap();
+        let read: LevelLayout = ron::from_str(&ron).unwrap();
+        assert_eq!(&read, level, "{ron}");
+    }
+
+    #[test]
+    fn to_ron_round_trips_shipped_levels() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/levels");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "ron")
+                || path.ends_with("manifest.ron")
+            {
+                continue;
+            }
+            let level: LevelLayout = ron::de::from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+            assert_round_trip(&level);
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn to_ron_keeps_every_field() {
+        let level: LevelLayout = ron::from_str(
+            "(
+                board_size: (3, 2),
+                objects: {
+                    (x:0, y:0): BombTimed,
+                    (x:1, y:0): Mirror(Rising),
+                    (x:2, y:1): Enemy,
+                },
+                fire_coords: [(x:0, y:0), (x:1, y:1)],
+                inventory: Some([(item: BombCone(Up), count: Some(2)), (item: BombLarge)]),
+                enemy_behavior: ChaseJewel,
+                voids: AbsorbBlasts,
+                fuse_ticks: 5,
+                hard_rock_hits: 1,
+                topology: Torus,
+                meta: (name: \"Every Field\", min_bombs: 1, min_affected_cells: 4),
+            )",
+        )
+        .unwrap();
+        assert_round_trip(&level);
+    }
+}
@@ -0,0 +1,18 @@
+//! The level model and chain reaction rules shared by the game and the level
+//! generator.
+
+mod grid_text;
+mod item;
+mod level;
+mod manifest;
+mod share_code;
+mod simulation;
+
+pub use grid_text::GridTextError;
+pub use item::{Facing, Item, MirrorTilt};
+pub use level::{
+    EnemyBehavior, GridCoord, InventorySlot, LevelLayout, LevelMetaData, Topology, VoidBehavior,
+};
+pub use manifest::{LevelManifest, LevelPack, UnlockRule};
+pub use share_code::{ShareCodeError, decode_share_code, encode_share_code};
+pub use simulation::{Board, ChainOutcome, Simulation, SimulationOutcome, TickEvents};
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+/// The levels of the game in play order, grouped into packs.
+///
+/// Progress is saved by the position of a level in this list, so new levels should be added
+/// at the end of a pack and packs at the end of the list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelManifest {
+    pub packs: Vec<LevelPack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelPack {
+    pub name: String,
+    /// What opens the pack.
+    #[serde(default)]
+    pub unlock: UnlockRule,
+    /// Whether each level of the pack opens only once the previous one is cleared.
+    #[serde(default)]
+    pub sequential: bool,
+    /// Level files, relative to the manifest.
+    pub levels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Reflect))]
+pub enum UnlockRule {
+    /// Always open.
+    #[default]
+    Open,
+    /// Opens once this many stars were earned over all levels.
+    Stars(u32),
+    /// Opens once every level of the named pack is cleared.
+    AfterPack(String),
+}
+
+impl LevelManifest {
+    /// Every level file in play order.
+    pub fn level_paths(&self) -> impl Iterator<Item = &str> {
+        self.packs
+            .iter()
+            .flat_map(|pack| pack.levels.iter().map(String::as_str))
+    }
+}
@@ -0,0 +1,453 @@
+//! Share codes, short texts carrying a level and optionally a placement of bombs on it.
+//!
+//! A code is the base32 text of a small binary encoding, starting with its format version and
+//! ending with a checksum. The base32 alphabet leaves out `I`, `L`, `O` and `U`, and decoding
+//! reads the first three as the digits they look like, ignores case, spaces and dashes.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    EnemyBehavior, Facing, GridCoord, InventorySlot, Item, LevelLayout, LevelMetaData, MirrorTilt,
+    Topology, VoidBehavior,
+};
+
+/// The version of the binary encoding written by [`encode_share_code`].
+const VERSION: u8 = 1;
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const FLAG_INVENTORY: u8 = 1 << 0;
+const FLAG_CHASE_JEWEL: u8 = 1 << 1;
+const FLAG_ABSORB_BLASTS: u8 = 1 << 2;
+const FLAG_TORUS: u8 = 1 << 3;
+
+/// Why a share code can't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareCodeError {
+    InvalidCharacter(char),
+    /// The code was mistyped or cut short.
+    Checksum,
+    /// The code was made by a newer version of the game.
+    UnsupportedVersion(u8),
+    UnknownItem(u8),
+    InvalidName,
+    /// The data ends before the level does, goes on after it, or puts something off the board.
+    Malformed,
+}
+
+impl fmt::Display for ShareCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareCodeError::InvalidCharacter(c) => write!(f, "invalid character {c:?}"),
+            ShareCodeError::Checksum => write!(f, "the code is mistyped or incomplete"),
+            ShareCodeError::UnsupportedVersion(version) => {
+                write!(f, "the code has the unsupported version {version}")
+            }
+            ShareCodeError::UnknownItem(code) => write!(f, "unknown item {code}"),
+            ShareCodeError::InvalidName => write!(f, "the level name is not valid text"),
+            ShareCodeError::Malformed => write!(f, "the code does not hold a level"),
+        }
+    }
+}
+
+impl std::error::Error for ShareCodeError {}
+
+/// Encodes `level` and the bombs the player placed on it. Names longer than 255 bytes are cut.
+pub fn encode_share_code(level: &LevelLayout, placements: &[(GridCoord, Item)]) -> String {
+    let mut bytes = vec![VERSION];
+
+    let (width, height) = level.board_size;
+    let mut flags = 0;
+    if level.inventory.is_some() {
+        flags |= FLAG_INVENTORY;
+    }
+    if level.enemy_behavior == EnemyBehavior::ChaseJewel {
+        flags |= FLAG_CHASE_JEWEL;
+    }
+    if level.voids == VoidBehavior::AbsorbBlasts {
+        flags |= FLAG_ABSORB_BLASTS;
+    }
+    if level.topology == Topology::Torus {
+        flags |= FLAG_TORUS;
+    }
+    bytes.extend([
+        width,
+        height,
+        flags,
+        level.fuse_ticks,
+        level.hard_rock_hits,
+        level.meta.min_bombs,
+        level.meta.min_affected_cells,
+    ]);
+
+    let mut name_len = level.meta.name.len().min(u8::MAX as usize);
+    while !level.meta.name.is_char_boundary(name_len) {
+        name_len -= 1;
+    }
+    bytes.push(name_len as u8);
+    bytes.extend_from_slice(&level.meta.name.as_bytes()[..name_len]);
+
+    // sorted row by row so that the same level always gets the same code
+    let mut objects: Vec<_> = level.objects.iter().map(|(&c, &item)| (c, item)).collect();
+    objects.sort_by_key(|(coord, _)| (coord.y, coord.x));
+    write_cells(&mut bytes, &objects);
+
+    bytes.extend((level.fire_coords.len() as u16).to_le_bytes());
+    for coord in &level.fire_coords {
+        bytes.extend([coord.x, coord.y]);
+    }
+
+    if let Some(inventory) = &level.inventory {
+        bytes.push(inventory.len() as u8);
+        for slot in inventory {
+            bytes.push(item_code(slot.item));
+            match slot.count {
+                Some(count) => bytes.extend([1, count]),
+                None => bytes.push(0),
+            }
+        }
+    }
+
+    write_cells(&mut bytes, placements);
+
+    bytes.push(checksum(&bytes));
+    to_base32(&bytes)
+}
+
+/// Decodes a code made by [`encode_share_code`] into the level and the placements it carries.
+pub fn decode_share_code(
+    code: &str,
+) -> Result<(LevelLayout, Vec<(GridCoord, Item)>), ShareCodeError> {
+    let mut bytes = from_base32(code)?;
+    let Some(sum) = bytes.pop() else {
+        return Err(ShareCodeError::Malformed);
+    };
+    if checksum(&bytes) != sum {
+        return Err(ShareCodeError::Checksum);
+    }
+
+    let mut reader = ByteReader(&bytes);
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(ShareCodeError::UnsupportedVersion(version));
+    }
+
+    let [
+        width,
+        height,
+        flags,
+        fuse_ticks,
+        hard_rock_hits,
+        min_bombs,
+        min_affected_cells,
+    ] = reader.array()?;
+    let name_len = reader.u8()?;
+    let name = String::from_utf8(reader.take(name_len as usize)?.to_vec())
+        .map_err(|_| ShareCodeError::InvalidName)?;
+
+    let objects: HashMap<_, _> = read_cells(&mut reader)?.into_iter().collect();
+
+    let fire_count = u16::from_le_bytes(reader.array()?);
+    let fire_coords: Vec<_> = (0..fire_count)
+        .map(|_| reader.array().map(|[x, y]| GridCoord { x, y }))
+        .collect::<Result<_, _>>()?;
+
+    let inventory = if flags & FLAG_INVENTORY != 0 {
+        let slot_count = reader.u8()?;
+        let slots = (0..slot_count)
+            .map(|_| {
+                let item = item_from_code(reader.u8()?)?;
+                let count = match reader.u8()? {
+                    0 => None,
+                    _ => Some(reader.u8()?),
+                };
+                Ok(InventorySlot { item, count })
+            })
+            .collect::<Result<_, _>>()?;
+        Some(slots)
+    } else {
+        None
+    };
+
+    let placements = read_cells(&mut reader)?;
+    if !reader.0.is_empty() {
+        return Err(ShareCodeError::Malformed);
+    }
+
+    // the checksum only catches typos, a made-up code could still reach outside the board
+    let is_on_board = |coord: &GridCoord| coord.x < width && coord.y < height;
+    if width == 0
+        || height == 0
+        || !objects.keys().all(is_on_board)
+        || !fire_coords.iter().all(is_on_board)
+        || !placements.iter().all(|(coord, _)| is_on_board(coord))
+    {
+        return Err(ShareCodeError::Malformed);
+    }
+
+    let level = LevelLayout {
+        board_size: (width, height),
+        objects,
+        fire_coords,
+        inventory,
+        enemy_behavior: if flags & FLAG_CHASE_JEWEL != 0 {
+            EnemyBehavior::ChaseJewel
+        } else {
+            EnemyBehavior::Idle
+        },
+        voids: if flags & FLAG_ABSORB_BLASTS != 0 {
+            VoidBehavior::AbsorbBlasts
+        } else {
+            VoidBehavior::PassBlasts
+        },
+        fuse_ticks,
+        hard_rock_hits,
+        topology: if flags & FLAG_TORUS != 0 {
+            Topology::Torus
+        } else {
+            Topology::Bounded
+        },
+        meta: LevelMetaData {
+            name,
+            min_bombs,
+            min_affected_cells,
+        },
+    };
+    Ok((level, placements))
+}
+
+fn write_cells(bytes: &mut Vec<u8>, cells: &[(GridCoord, Item)]) {
+    bytes.extend((cells.len() as u16).to_le_bytes());
+    for (coord, item) in cells {
+        bytes.extend([coord.x, coord.y, item_code(*item)]);
+    }
+}
+
+fn read_cells(reader: &mut ByteReader) -> Result<Vec<(GridCoord, Item)>, ShareCodeError> {
+    let count = u16::from_le_bytes(reader.array()?);
+    (0..count)
+        .map(|_| {
+            let [x, y, code] = reader.array()?;
+            Ok((GridCoord { x, y }, item_from_code(code)?))
+        })
+        .collect()
+}
+
+/// The items in the order of their codes. New items must be added at the end.
+const ITEMS: [Item; 19] = [
+    Item::BombSmall,
+    Item::BombMedium,
+    Item::BombLarge,
+    Item::BombHorizontal,
+    Item::BombVertical,
+    Item::Null,
+    Item::Rock,
+    Item::Jewel,
+    Item::Eraser,
+    Item::Enemy,
+    Item::Wall,
+    Item::BombTimed,
+    Item::HardRock,
+    Item::BombCone(Facing::Up),
+    Item::BombCone(Facing::Right),
+    Item::BombCone(Facing::Down),
+    Item::BombCone(Facing::Left),
+    Item::Mirror(MirrorTilt::Rising),
+    Item::Mirror(MirrorTilt::Falling),
+];
+
+fn item_code(item: Item) -> u8 {
+    ITEMS
+        .iter()
+        .position(|&listed| listed == item)
+        .expect("every item is listed") as u8
+}
+
+fn item_from_code(code: u8) -> Result<Item, ShareCodeError> {
+    ITEMS
+        .get(code as usize)
+        .copied()
+        .ok_or(ShareCodeError::UnknownItem(code))
+}
+
+/// A byte sum that catches most typos.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, &byte| sum.rotate_left(1).wrapping_add(byte))
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl ByteReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], ShareCodeError> {
+        if self.0.len() < len {
+            return Err(ShareCodeError::Malformed);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ShareCodeError> {
+        self.array().map(|[byte]| byte)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ShareCodeError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+fn from_base32(code: &str) -> Result<Vec<u8>, ShareCodeError> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in code.chars() {
+        if c.is_whitespace() || c == '-' {
+            continue;
+        }
+        let digit = match c.to_ascii_uppercase() {
+            'O' => 0,
+            'I' | 'L' => 1,
+            upper => ALPHABET
+                .iter()
+                .position(|&a| a as char == upper)
+                .ok_or(ShareCodeError::InvalidCharacter(c))?,
+        };
+        buffer = (buffer << 5) | digit as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shipped_levels() -> Vec<LevelLayout> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/levels");
+        let levels: Vec<LevelLayout> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension().is_some_and(|extension| extension == "ron")
+                    && !path.ends_with("manifest.ron")
+            })
+            .map(|path| ron::de::from_bytes(&std::fs::read(path).unwrap()).unwrap())
+            .collect();
+        assert!(!levels.is_empty());
+        levels
+    }
+
+    /// A small bomb on every empty cell, so that the placements cover the whole board.
+    fn fill_empty_cells(level: &LevelLayout) -> Vec<(GridCoord, Item)> {
+        level
+            .cells()
+            .filter(|coord| !level.objects.contains_key(coord))
+            .map(|coord| (coord, Item::BombSmall))
+            .collect()
+    }
+
+    fn assert_round_trip(level: &LevelLayout, placements: &[(GridCoord, Item)]) {
+        let code = encode_share_code(level, placements);
+        let (read_level, read_placements) = decode_share_code(&code).unwrap();
+        assert_eq!(&read_level, level, "{code}");
+        assert_eq!(read_placements, placements, "{code}");
+    }
+
+    /// Encodes the raw bytes of a code the way [`encode_share_code`] finishes one.
+    fn code_from_bytes(mut bytes: Vec<u8>) -> String {
+        bytes.push(checksum(&bytes));
+        to_base32(&bytes)
+    }
+
+    #[test]
+    fn shipped_levels_round_trip() {
+        for level in shipped_levels() {
+            assert_round_trip(&level, &[]);
+            assert_round_trip(&level, &fill_empty_cells(&level));
+        }
+    }
+
+    #[test]
+    fn changed_character_fails_checksum() {
+        for level in shipped_levels() {
+            let code = encode_share_code(&level, &fill_empty_cells(&level));
+            let mut chars: Vec<char> = code.chars().collect();
+            // the first character only holds the top of the version byte
+            chars[1] = if chars[1] == '0' { '1' } else { '0' };
+            let changed: String = chars.into_iter().collect();
+            assert_eq!(
+                decode_share_code(&changed),
+                Err(ShareCodeError::Checksum),
+                "{changed}"
+            );
+        }
+    }
+
+    #[test]
+    fn lookalike_letters_read_as_digits() {
+        let level = &shipped_levels()[0];
+        let code = encode_share_code(level, &[]);
+        let lookalike = code.replace('0', "o").replace('1', "L");
+        assert_eq!(decode_share_code(&lookalike).unwrap().0, *level);
+    }
+
+    #[test]
+    fn empty_board_is_malformed() {
+        // version, 0x3 board, no flags, fuse, hits, meta, empty name, no objects or fires,
+        // no placements
+        let code = code_from_bytes(vec![VERSION, 0, 3, 0, 3, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(decode_share_code(&code), Err(ShareCodeError::Malformed));
+    }
+
+    #[test]
+    fn cells_off_the_board_are_malformed() {
+        let level = &shipped_levels()[0];
+        let (width, height) = level.board_size;
+        let off_board = [
+            GridCoord { x: width, y: 0 },
+            GridCoord { x: 0, y: height },
+            GridCoord {
+                x: u8::MAX,
+                y: u8::MAX,
+            },
+        ];
+        for coord in off_board {
+            let code = encode_share_code(level, &[(coord, Item::BombSmall)]);
+            assert_eq!(decode_share_code(&code), Err(ShareCodeError::Malformed));
+
+            let mut with_object = level.clone();
+            with_object.objects.insert(coord, Item::Rock);
+            let code = encode_share_code(&with_object, &[]);
+            assert_eq!(decode_share_code(&code), Err(ShareCodeError::Malformed));
+
+            let mut with_fire = level.clone();
+            with_fire.fire_coords.push(coord);
+            let code = encode_share_code(&with_fire, &[]);
+            assert_eq!(decode_share_code(&code), Err(ShareCodeError::Malformed));
+        }
+    }
+}
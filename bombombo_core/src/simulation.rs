@@ -0,0 +1,1066 @@
+//! A headless model of the chain reaction.
+//!
+//! [`Simulation`] owns the rules of the run phase. The run phase replays the
+//! [`TickEvents`] it produces, and the result screen scores its
+//! [`SimulationOutcome`], so both always agree on what happened.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{EnemyBehavior, GridCoord, Item, LevelLayout, Topology, VoidBehavior};
+
+/// A level together with the player's placements.
+#[derive(Debug, Clone)]
+pub struct Board {
+    size: (u8, u8),
+    objects: HashMap<GridCoord, Item>,
+    placements: Vec<(GridCoord, Item)>,
+    fire_coords: Vec<GridCoord>,
+    enemy_behavior: EnemyBehavior,
+    voids: VoidBehavior,
+    fuse_ticks: u8,
+    hard_rock_hits: u8,
+    topology: Topology,
+}
+
+impl Board {
+    pub fn new(
+        level_layout: &LevelLayout,
+        placements: impl IntoIterator<Item = (GridCoord, Item)>,
+    ) -> Self {
+        let mut objects = level_layout.objects.clone();
+        let placements: Vec<_> = placements
+            .into_iter()
+            .filter(|&(coord, item)| item != Item::Eraser && !objects.contains_key(&coord))
+            .collect();
+        objects.extend(placements.iter().copied());
+
+        Self {
+            size: level_layout.board_size,
+            objects,
+            placements,
+            fire_coords: level_layout.fire_coords.clone(),
+            enemy_behavior: level_layout.enemy_behavior,
+            voids: level_layout.voids,
+            fuse_ticks: level_layout.fuse_ticks,
+            hard_rock_hits: level_layout.hard_rock_hits,
+            topology: level_layout.topology,
+        }
+    }
+
+    pub fn size(&self) -> (u8, u8) {
+        self.size
+    }
+
+    pub fn objects(&self) -> &HashMap<GridCoord, Item> {
+        &self.objects
+    }
+
+    /// Items added by the player on top of the level layout.
+    pub fn placements(&self) -> &[(GridCoord, Item)] {
+        &self.placements
+    }
+
+    pub fn fire_coords(&self) -> &[GridCoord] {
+        &self.fire_coords
+    }
+
+    pub fn enemy_behavior(&self) -> EnemyBehavior {
+        self.enemy_behavior
+    }
+
+    /// Ticks between the ignition and the explosion of `item`.
+    pub fn fuse_ticks(&self, item: Item) -> u8 {
+        match item {
+            Item::BombTimed => self.fuse_ticks.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Blasts needed to destroy `item`.
+    pub fn hits_to_destroy(&self, item: Item) -> u8 {
+        match item {
+            Item::HardRock => self.hard_rock_hits.max(1),
+            _ => 1,
+        }
+    }
+
+    pub fn contains(&self, coord: GridCoord) -> bool {
+        coord.x < self.size.0 && coord.y < self.size.1
+    }
+
+    /// Whether `coord` is a void cell cut out of the board.
+    pub fn is_void(&self, coord: GridCoord) -> bool {
+        self.objects.get(&coord) == Some(&Item::Null)
+    }
+
+    /// Moves `coord` by `(dx, dy)`, or returns `None` if it leaves a bounded board. On a torus
+    /// it comes back on the other side instead.
+    pub fn offset(&self, coord: GridCoord, (dx, dy): (i8, i8)) -> Option<GridCoord> {
+        match self.topology {
+            Topology::Bounded => {
+                let x = u8::try_from(coord.x as i16 + dx as i16).ok()?;
+                let y = u8::try_from(coord.y as i16 + dy as i16).ok()?;
+                let coord = GridCoord { x, y };
+                self.contains(coord).then_some(coord)
+            }
+            Topology::Torus => {
+                let wrap = |value: u8, delta: i8, size: u8| {
+                    (value as i16 + delta as i16).rem_euclid(size as i16) as u8
+                };
+                Some(GridCoord {
+                    x: wrap(coord.x, dx, self.size.0),
+                    y: wrap(coord.y, dy, self.size.1),
+                })
+            }
+        }
+    }
+
+    /// The shortest way from `from` to `to` along each axis, which may cross the edges of a
+    /// torus.
+    pub fn delta(&self, from: GridCoord, to: GridCoord) -> (i16, i16) {
+        let axis = |from: u8, to: u8, size: u8| {
+            let delta = to as i16 - from as i16;
+            match self.topology {
+                Topology::Bounded => delta,
+                Topology::Torus => {
+                    let size = size as i16;
+                    let delta = delta.rem_euclid(size);
+                    if delta > size / 2 {
+                        delta - size
+                    } else {
+                        delta
+                    }
+                }
+            }
+        };
+        (
+            axis(from.x, to.x, self.size.0),
+            axis(from.y, to.y, self.size.1),
+        )
+    }
+
+    /// Steps between two cells moving one cell at a time along the axes.
+    pub fn distance(&self, a: GridCoord, b: GridCoord) -> u16 {
+        let (dx, dy) = self.delta(a, b);
+        dx.unsigned_abs() + dy.unsigned_abs()
+    }
+
+    /// Cells on the board hit when `item` explodes at `coord`, each listed once.
+    ///
+    /// Line blasts are traced cell by cell: they stop at the first wall and turn at mirrors,
+    /// both of which are hit themselves.
+    pub fn impact_area(&self, coord: GridCoord, item: Item) -> impl Iterator<Item = GridCoord> {
+        let mut cells: Vec<_> = if item.is_line_bomb() {
+            self.trace_line_blast(coord, item)
+        } else {
+            item.impact_zone()
+                .iter()
+                .filter(|&&delta| !self.is_shielded(coord, delta))
+                .filter_map(|&delta| self.offset(coord, delta))
+                .filter(|&affected| !self.is_void(affected))
+                .collect()
+        };
+        // mirrors, and the edges of a torus, can bring a blast back over cells it already hit
+        let mut seen = HashSet::new();
+        cells.retain(|&cell| seen.insert(cell));
+        cells.into_iter()
+    }
+
+    /// Mirrors turning the blast of the line bomb `item` exploding at `coord`.
+    pub fn deflecting_mirrors(&self, coord: GridCoord, item: Item) -> Vec<GridCoord> {
+        if !item.is_line_bomb() {
+            return Vec::new();
+        }
+        self.impact_area(coord, item)
+            .filter(|cell| matches!(self.objects.get(cell), Some(Item::Mirror(_))))
+            .collect()
+    }
+
+    /// Follows the blast of a line bomb in both directions from `coord`, as far as the line
+    /// bombs reach.
+    fn trace_line_blast(&self, coord: GridCoord, item: Item) -> Vec<GridCoord> {
+        let reach = item
+            .impact_zone()
+            .iter()
+            .map(|&(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()))
+            .max()
+            .unwrap_or_default();
+        let directions = match item {
+            Item::BombHorizontal => [(1, 0), (-1, 0)],
+            _ => [(0, 1), (0, -1)],
+        };
+
+        let mut cells = vec![coord];
+        for mut direction in directions {
+            let mut cell = coord;
+            for _ in 0..reach {
+                let Some(next) = self.offset(cell, direction) else {
+                    break;
+                };
+                cell = next;
+                match self.objects.get(&cell) {
+                    Some(Item::Null) if self.voids.passes_blasts() => continue,
+                    Some(Item::Null) => break,
+                    Some(Item::Wall) => {
+                        cells.push(cell);
+                        break;
+                    }
+                    Some(Item::Mirror(tilt)) => direction = tilt.deflect(direction),
+                    _ => {}
+                }
+                cells.push(cell);
+            }
+        }
+        cells
+    }
+
+    /// Whether a void cell stops the blast from `coord` before it reaches `delta`.
+    ///
+    /// The blast travels along the longer axis first and diagonally once both are equal.
+    fn is_shielded(&self, coord: GridCoord, (mut dx, mut dy): (i8, i8)) -> bool {
+        let blocks = |cell: GridCoord| self.is_void(cell) && !self.voids.passes_blasts();
+
+        loop {
+            match dx.abs().cmp(&dy.abs()) {
+                Ordering::Greater => dx -= dx.signum(),
+                Ordering::Less => dy -= dy.signum(),
+                Ordering::Equal => (dx, dy) = (dx - dx.signum(), dy - dy.signum()),
+            }
+            if (dx, dy) == (0, 0) {
+                return false;
+            }
+            if self.offset(coord, (dx, dy)).is_some_and(blocks) {
+                return true;
+            }
+        }
+    }
+}
+
+/// What happened during a single tick of the chain reaction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickEvents {
+    pub tick: u32,
+    /// Bombs that exploded this tick.
+    pub exploded: Vec<(GridCoord, Item)>,
+    /// Non-bomb objects caught in a blast.
+    pub destroyed: Vec<(GridCoord, Item)>,
+    /// Hard rocks hit but still standing, with the blasts left to destroy them.
+    pub cracked: Vec<(GridCoord, u8)>,
+    /// Bombs caught in a blast. They explode on the next tick, or after their fuse for timed
+    /// bombs.
+    pub ignited: Vec<(GridCoord, Item)>,
+    /// Burning timed bombs, with the ticks left until they explode.
+    pub fuses: Vec<(GridCoord, u8)>,
+    /// Cells hit this tick, with the number of overlapping blasts.
+    pub affected: Vec<(GridCoord, usize)>,
+    /// Mirrors that turned a line blast this tick.
+    pub deflected: Vec<GridCoord>,
+    /// Enemies that stepped from the first cell to the second after the blasts.
+    pub moved: Vec<(GridCoord, GridCoord)>,
+    /// Jewels an enemy stepped onto. They are lost.
+    pub stolen: Vec<GridCoord>,
+}
+
+/// The final state of a finished chain reaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    pub is_cleared: bool,
+    pub used_bomb_count: u8,
+    pub affected_cell_count: u8,
+}
+
+/// What the chain started by one fire reached. A bomb or cell hit by several chains at once
+/// is credited to the one whose fire comes first in the level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainOutcome {
+    pub fire_coord: GridCoord,
+    pub exploded_bomb_count: u8,
+    pub affected_cell_count: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    board: Board,
+    remaining: HashMap<GridCoord, Item>,
+    /// Ignited bombs with the ticks left until they explode.
+    burning: HashMap<GridCoord, u8>,
+    /// The chain, as an index into the fires, that ignited each bomb.
+    chains: HashMap<GridCoord, usize>,
+    /// Cells hit so far, with the chain that hit them first.
+    affected_cells: HashMap<GridCoord, usize>,
+    /// Blasts taken so far by objects that are still standing.
+    damage: HashMap<GridCoord, u8>,
+    tick: u32,
+}
+
+impl Simulation {
+    pub fn new(board: Board) -> Self {
+        let mut burning = HashMap::new();
+        let mut chains = HashMap::new();
+        for (chain, &coord) in board.fire_coords.iter().enumerate() {
+            let Some(&item) = board.objects.get(&coord).filter(|item| item.is_bomb()) else {
+                continue;
+            };
+            burning
+                .entry(coord)
+                .or_insert_with(|| board.fuse_ticks(item));
+            chains.entry(coord).or_insert(chain);
+        }
+
+        Self {
+            remaining: board.objects.clone(),
+            board,
+            burning,
+            chains,
+            affected_cells: HashMap::new(),
+            damage: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Whether no bomb is left burning.
+    pub fn is_finished(&self) -> bool {
+        self.burning.is_empty()
+    }
+
+    /// Explodes every burning bomb and returns what happened.
+    pub fn step(&mut self) -> TickEvents {
+        self.tick += 1;
+
+        self.burning.values_mut().for_each(|ticks| *ticks -= 1);
+        let mut burning: Vec<_> = self
+            .burning
+            .iter()
+            .filter(|&(_, &ticks)| ticks == 0)
+            .map(|(&coord, _)| coord)
+            .collect();
+        self.burning.retain(|_, ticks| *ticks > 0);
+        burning.sort_by_key(|coord| (coord.x, coord.y));
+
+        let exploded: Vec<_> = burning
+            .into_iter()
+            .filter_map(|coord| self.remaining.remove(&coord).map(|item| (coord, item)))
+            .collect();
+
+        // count the overlapping blasts on each cell, and the first chain among them
+        let mut hits: HashMap<GridCoord, (usize, usize)> = HashMap::new();
+        for &(coord, item) in &exploded {
+            let chain = self.chains[&coord];
+            for affected_coord in self.board.impact_area(coord, item) {
+                let (count, first_chain) = hits.entry(affected_coord).or_insert((0, chain));
+                *count += 1;
+                *first_chain = (*first_chain).min(chain);
+            }
+        }
+        let mut deflected: Vec<_> = exploded
+            .iter()
+            .flat_map(|&(coord, item)| self.board.deflecting_mirrors(coord, item))
+            .collect();
+        deflected.sort_by_key(|coord| (coord.x, coord.y));
+        deflected.dedup();
+
+        let mut affected: Vec<_> = hits
+            .iter()
+            .map(|(&coord, &(count, _))| (coord, count))
+            .collect();
+        affected.sort_by_key(|&(coord, _)| (coord.x, coord.y));
+
+        let mut destroyed = Vec::new();
+        let mut cracked = Vec::new();
+        let mut ignited = Vec::new();
+        for &(coord, count) in &affected {
+            let chain = hits[&coord].1;
+            self.affected_cells.entry(coord).or_insert(chain);
+            let Some(&item) = self.remaining.get(&coord) else {
+                continue;
+            };
+            if item.is_bomb() {
+                if !self.burning.contains_key(&coord) {
+                    ignited.push((coord, item));
+                    self.burning.insert(coord, self.board.fuse_ticks(item));
+                    self.chains.insert(coord, chain);
+                }
+            } else if !item.is_indestructible() {
+                let damage = self.damage.entry(coord).or_default();
+                *damage = damage.saturating_add(u8::try_from(count).unwrap_or(u8::MAX));
+                let hits_left = self.board.hits_to_destroy(item).saturating_sub(*damage);
+                if hits_left == 0 {
+                    destroyed.push((coord, item));
+                    self.remaining.remove(&coord);
+                } else {
+                    cracked.push((coord, hits_left));
+                }
+            }
+        }
+
+        let (moved, stolen) = match self.board.enemy_behavior {
+            EnemyBehavior::Idle => Default::default(),
+            EnemyBehavior::ChaseJewel => self.move_enemies(),
+        };
+
+        let mut fuses: Vec<_> = self
+            .burning
+            .iter()
+            .filter(|&(coord, _)| self.remaining.get(coord) == Some(&Item::BombTimed))
+            .map(|(&coord, &ticks)| (coord, ticks))
+            .collect();
+        fuses.sort_by_key(|&(coord, _)| (coord.x, coord.y));
+
+        TickEvents {
+            tick: self.tick,
+            exploded,
+            destroyed,
+            cracked,
+            ignited,
+            fuses,
+            affected,
+            deflected,
+            moved,
+            stolen,
+        }
+    }
+
+    /// Steps every remaining enemy one cell toward its nearest jewel, along the axis with the
+    /// longer way to go first. Enemies don't walk into other objects, except the jewel itself.
+    fn move_enemies(&mut self) -> (Vec<(GridCoord, GridCoord)>, Vec<GridCoord>) {
+        let mut enemies: Vec<_> = self
+            .remaining
+            .iter()
+            .filter(|&(_, &item)| item == Item::Enemy)
+            .map(|(&coord, _)| coord)
+            .collect();
+        enemies.sort_by_key(|coord| (coord.x, coord.y));
+
+        let mut moved = Vec::new();
+        let mut stolen = Vec::new();
+        for enemy in enemies {
+            let Some(jewel) = self
+                .remaining
+                .iter()
+                .filter(|&(_, &item)| item == Item::Jewel)
+                .map(|(&coord, _)| coord)
+                .min_by_key(|&coord| (self.board.distance(enemy, coord), coord.x, coord.y))
+            else {
+                break;
+            };
+
+            let (dx, dy) = self.board.delta(enemy, jewel);
+            let longer_x = dx.abs() >= dy.abs();
+            let (dx, dy) = (dx.signum() as i8, dy.signum() as i8);
+            let steps = if longer_x {
+                [(dx, 0), (0, dy)]
+            } else {
+                [(0, dy), (dx, 0)]
+            };
+            let Some(next) = steps
+                .into_iter()
+                .filter(|&step| step != (0, 0))
+                .filter_map(|step| self.board.offset(enemy, step))
+                .find(|next| {
+                    self.remaining
+                        .get(next)
+                        .is_none_or(|&item| item == Item::Jewel)
+                })
+            else {
+                continue;
+            };
+
+            if self.remaining.insert(next, Item::Enemy) == Some(Item::Jewel) {
+                stolen.push(next);
+            }
+            self.remaining.remove(&enemy);
+            moved.push((enemy, next));
+        }
+        (moved, stolen)
+    }
+
+    /// Steps until no bomb is left burning.
+    pub fn run(&mut self) -> Vec<TickEvents> {
+        let mut events = Vec::new();
+        while !self.is_finished() {
+            events.push(self.step());
+        }
+        events
+    }
+
+    /// The outcome of each chain, in the order of the level's fires.
+    pub fn chain_outcomes(&self) -> Vec<ChainOutcome> {
+        self.board
+            .fire_coords
+            .iter()
+            .enumerate()
+            .map(|(chain, &fire_coord)| ChainOutcome {
+                fire_coord,
+                exploded_bomb_count: self
+                    .chains
+                    .iter()
+                    .filter(|&(coord, &c)| c == chain && !self.remaining.contains_key(coord))
+                    .count() as u8,
+                affected_cell_count: self
+                    .affected_cells
+                    .values()
+                    .filter(|&&c| c == chain)
+                    .count() as u8,
+            })
+            .collect()
+    }
+
+    pub fn outcome(&self) -> SimulationOutcome {
+        // Check if:
+        // - All bombs are burned
+        // - All rocks, hard rocks included, are destroyed
+        // - All jewels are saved
+        // - All enemies are defeated
+        // Enemies may have moved, so this looks at what remains rather than at the layout.
+        let count_jewels = |objects: &HashMap<GridCoord, Item>| {
+            objects
+                .values()
+                .filter(|&&item| item == Item::Jewel)
+                .count()
+        };
+        let is_cleared = self.remaining.values().all(|item| {
+            !item.is_bomb() && !matches!(item, Item::Rock | Item::HardRock | Item::Enemy)
+        }) && count_jewels(&self.remaining) == count_jewels(&self.board.objects);
+
+        SimulationOutcome {
+            is_cleared,
+            used_bomb_count: self
+                .board
+                .placements
+                .iter()
+                .filter(|(_, item)| item.is_bomb())
+                .count() as u8,
+            affected_cell_count: self.affected_cells.len() as u8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LevelMetaData, MirrorTilt};
+
+    fn coord(x: u8, y: u8) -> GridCoord {
+        GridCoord { x, y }
+    }
+
+    /// The impact area of `item` exploding at `coord`, row by row.
+    fn impact_area(board: &Board, coord: GridCoord, item: Item) -> Vec<GridCoord> {
+        let mut cells: Vec<_> = board.impact_area(coord, item).collect();
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        cells
+    }
+
+    fn level(objects: &[(GridCoord, Item)], fire_coords: &[GridCoord]) -> LevelLayout {
+        LevelLayout {
+            board_size: (5, 5),
+            objects: objects.iter().copied().collect(),
+            fire_coords: fire_coords.to_vec(),
+            inventory: None,
+            enemy_behavior: EnemyBehavior::Idle,
+            voids: VoidBehavior::PassBlasts,
+            fuse_ticks: LevelLayout::DEFAULT_FUSE_TICKS,
+            hard_rock_hits: LevelLayout::DEFAULT_HARD_ROCK_HITS,
+            topology: Topology::Bounded,
+            meta: LevelMetaData {
+                name: "Test".to_string(),
+                min_bombs: 0,
+                min_affected_cells: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn direct_blast() {
+        let level = level(
+            &[(coord(0, 0), Item::BombSmall), (coord(1, 1), Item::Rock)],
+            &[coord(0, 0)],
+        );
+        let mut simulation = Simulation::new(Board::new(&level, []));
+
+        let events = simulation.step();
+        assert_eq!(events.tick, 1);
+        assert_eq!(events.exploded, [(coord(0, 0), Item::BombSmall)]);
+        assert_eq!(events.destroyed, [(coord(1, 1), Item::Rock)]);
+        assert!(events.ignited.is_empty());
+        assert_eq!(
+            events.affected,
+            [
+                (coord(0, 0), 1),
+                (coord(0, 1), 1),
+                (coord(1, 0), 1),
+                (coord(1, 1), 1)
+            ]
+        );
+
+        assert!(simulation.is_finished());
+        assert_eq!(
+            simulation.outcome(),
+            SimulationOutcome {
+                is_cleared: true,
+                used_bomb_count: 0,
+                affected_cell_count: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn chain() {
+        let level = level(
+            &[(coord(0, 0), Item::BombSmall), (coord(2, 1), Item::Rock)],
+            &[coord(0, 0)],
+        );
+        let board = Board::new(&level, [(coord(1, 0), Item::BombSmall)]);
+        let mut simulation = Simulation::new(board);
+
+        let events = simulation.step();
+        assert_eq!(events.exploded, [(coord(0, 0), Item::BombSmall)]);
+        assert_eq!(events.ignited, [(coord(1, 0), Item::BombSmall)]);
+        assert!(events.destroyed.is_empty());
+        assert!(!simulation.is_finished());
+
+        let events = simulation.step();
+        assert_eq!(events.tick, 2);
+        assert_eq!(events.exploded, [(coord(1, 0), Item::BombSmall)]);
+        assert_eq!(events.destroyed, [(coord(2, 1), Item::Rock)]);
+        assert!(events.ignited.is_empty());
+
+        assert!(simulation.is_finished());
+        assert_eq!(
+            simulation.outcome(),
+            SimulationOutcome {
+                is_cleared: true,
+                used_bomb_count: 1,
+                affected_cell_count: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn failed_level() {
+        let level = level(
+            &[(coord(0, 0), Item::BombSmall), (coord(4, 4), Item::Rock)],
+            &[coord(0, 0)],
+        );
+        // the placed bomb is out of reach of the fire
+        let board = Board::new(&level, [(coord(3, 0), Item::BombSmall)]);
+        let mut simulation = Simulation::new(board);
+
+        let events = simulation.run();
+        assert_eq!(events.len(), 1);
+        assert!(simulation.is_finished());
+        assert_eq!(
+            simulation.outcome(),
+            SimulationOutcome {
+                is_cleared: false,
+                used_bomb_count: 1,
+                affected_cell_count: 4,
+            }
+        );
+    }
+
+    /// The run phase steps the simulation tick by tick and the result screen reads the chain
+    /// outcomes afterwards, so they have to add up to the replayed events.
+    #[test]
+    fn chain_outcomes_match_replay() {
+        let level = level(
+            &[
+                (coord(0, 0), Item::BombSmall),
+                (coord(4, 4), Item::BombSmall),
+                (coord(1, 1), Item::Rock),
+                (coord(2, 2), Item::Rock),
+            ],
+            &[coord(0, 0), coord(4, 4)],
+        );
+        let board = Board::new(&level, [(coord(3, 3), Item::BombMedium)]);
+        let mut simulation = Simulation::new(board);
+
+        let mut exploded = 0;
+        let mut affected = HashSet::new();
+        while !simulation.is_finished() {
+            let events = simulation.step();
+            exploded += events.exploded.len();
+            affected.extend(events.affected.iter().map(|&(coord, _)| coord));
+        }
+
+        let chain_outcomes = simulation.chain_outcomes();
+        assert_eq!(
+            chain_outcomes
+                .iter()
+                .map(|chain| chain.fire_coord)
+                .collect::<Vec<_>>(),
+            [coord(0, 0), coord(4, 4)]
+        );
+        assert_eq!(chain_outcomes[0].exploded_bomb_count, 1);
+        assert_eq!(chain_outcomes[1].exploded_bomb_count, 2);
+        assert_eq!(
+            chain_outcomes
+                .iter()
+                .map(|chain| chain.exploded_bomb_count as usize)
+                .sum::<usize>(),
+            exploded
+        );
+        assert_eq!(
+            chain_outcomes
+                .iter()
+                .map(|chain| chain.affected_cell_count as usize)
+                .sum::<usize>(),
+            affected.len()
+        );
+        assert_eq!(
+            simulation.outcome().affected_cell_count as usize,
+            affected.len()
+        );
+        assert!(simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn mirror_turns_line_blast() {
+        // a blast heading right along y = 2 turns towards +y at a rising mirror and towards -y
+        // at a falling one
+        let cases = [
+            (
+                MirrorTilt::Rising,
+                [
+                    coord(0, 2),
+                    coord(1, 2),
+                    coord(2, 2),
+                    coord(2, 3),
+                    coord(2, 4),
+                ],
+            ),
+            (
+                MirrorTilt::Falling,
+                [
+                    coord(2, 0),
+                    coord(2, 1),
+                    coord(0, 2),
+                    coord(1, 2),
+                    coord(2, 2),
+                ],
+            ),
+        ];
+        for (tilt, expected) in cases {
+            let level = level(
+                &[
+                    (coord(0, 2), Item::BombHorizontal),
+                    (coord(2, 2), Item::Mirror(tilt)),
+                ],
+                &[coord(0, 2)],
+            );
+            let mut simulation = Simulation::new(Board::new(&level, []));
+            assert_eq!(
+                impact_area(simulation.board(), coord(0, 2), Item::BombHorizontal),
+                expected,
+                "{tilt:?}"
+            );
+
+            let events = simulation.step();
+            assert_eq!(events.deflected, [coord(2, 2)], "{tilt:?}");
+            assert!(events.affected.contains(&(coord(2, 2), 1)), "{tilt:?}");
+            // the mirror is hit but stands
+            assert!(events.destroyed.is_empty(), "{tilt:?}");
+            assert!(simulation.outcome().is_cleared, "{tilt:?}");
+        }
+    }
+
+    #[test]
+    fn mirrors_bringing_blast_back_hit_cells_once() {
+        // three mirrors send the blast heading right around a square and back over the bomb
+        let level = level(
+            &[
+                (coord(1, 1), Item::BombHorizontal),
+                (coord(3, 1), Item::Mirror(MirrorTilt::Rising)),
+                (coord(3, 3), Item::Mirror(MirrorTilt::Falling)),
+                (coord(1, 3), Item::Mirror(MirrorTilt::Rising)),
+            ],
+            &[coord(1, 1)],
+        );
+        let mut simulation = Simulation::new(Board::new(&level, []));
+        assert_eq!(
+            impact_area(simulation.board(), coord(1, 1), Item::BombHorizontal),
+            [
+                coord(1, 0),
+                coord(0, 1),
+                coord(1, 1),
+                coord(2, 1),
+                coord(3, 1),
+                coord(1, 2),
+                coord(3, 2),
+                coord(1, 3),
+                coord(2, 3),
+                coord(3, 3),
+            ]
+        );
+
+        let events = simulation.step();
+        assert_eq!(events.deflected, [coord(1, 3), coord(3, 1), coord(3, 3)]);
+        assert!(events.affected.iter().all(|&(_, count)| count == 1));
+        assert_eq!(simulation.outcome().affected_cell_count, 10);
+    }
+
+    #[test]
+    fn line_blast_wraps_around_torus_once() {
+        let mut level = level(
+            &[
+                (coord(2, 2), Item::BombHorizontal),
+                (coord(0, 2), Item::Rock),
+            ],
+            &[coord(2, 2)],
+        );
+        level.topology = Topology::Torus;
+        let mut simulation = Simulation::new(Board::new(&level, []));
+        // the reach of 10 goes around the 5 wide board twice each way
+        assert_eq!(
+            impact_area(simulation.board(), coord(2, 2), Item::BombHorizontal),
+            [
+                coord(0, 2),
+                coord(1, 2),
+                coord(2, 2),
+                coord(3, 2),
+                coord(4, 2)
+            ]
+        );
+
+        let events = simulation.step();
+        assert_eq!(events.exploded, [(coord(2, 2), Item::BombHorizontal)]);
+        assert!(events.affected.iter().all(|&(_, count)| count == 1));
+        assert_eq!(events.destroyed, [(coord(0, 2), Item::Rock)]);
+        assert_eq!(simulation.outcome().affected_cell_count, 5);
+    }
+
+    #[test]
+    fn bounded_board_drops_cells_past_the_edge() {
+        let mut level = level(&[], &[]);
+        let board = Board::new(&level, []);
+        assert_eq!(board.offset(coord(0, 0), (-1, 0)), None);
+        assert_eq!(board.offset(coord(4, 4), (0, 1)), None);
+        assert_eq!(
+            impact_area(&board, coord(0, 0), Item::BombSmall),
+            [coord(0, 0), coord(1, 0), coord(0, 1), coord(1, 1)]
+        );
+
+        level.topology = Topology::Torus;
+        let board = Board::new(&level, []);
+        assert_eq!(board.offset(coord(0, 0), (-1, 0)), Some(coord(4, 0)));
+        assert_eq!(board.offset(coord(4, 4), (0, 1)), Some(coord(4, 0)));
+        assert_eq!(impact_area(&board, coord(0, 0), Item::BombSmall).len(), 9);
+    }
+
+    #[test]
+    fn delta_takes_the_short_way_across_torus_edges() {
+        let mut level = level(
+            &[
+                (coord(2, 0), Item::BombSmall),
+                (coord(0, 2), Item::Enemy),
+                (coord(3, 2), Item::Jewel),
+            ],
+            &[coord(2, 0)],
+        );
+        level.enemy_behavior = EnemyBehavior::ChaseJewel;
+
+        let board = Board::new(&level, []);
+        assert_eq!(board.delta(coord(0, 2), coord(3, 2)), (3, 0));
+        let mut simulation = Simulation::new(board);
+        assert_eq!(simulation.step().moved, [(coord(0, 2), coord(1, 2))]);
+
+        level.topology = Topology::Torus;
+        let board = Board::new(&level, []);
+        assert_eq!(board.delta(coord(0, 2), coord(3, 2)), (-2, 0));
+        assert_eq!(board.delta(coord(1, 4), coord(1, 0)), (0, 1));
+        assert_eq!(board.distance(coord(0, 2), coord(3, 2)), 2);
+        let mut simulation = Simulation::new(board);
+        assert_eq!(simulation.step().moved, [(coord(0, 2), coord(4, 2))]);
+    }
+
+    #[test]
+    fn hard_rock_breaks_after_enough_blasts() {
+        let mut level = level(
+            &[
+                (coord(0, 0), Item::BombSmall),
+                (coord(1, 1), Item::HardRock),
+            ],
+            &[coord(0, 0)],
+        );
+        level.hard_rock_hits = 2;
+        let board = Board::new(&level, [(coord(1, 0), Item::BombSmall)]);
+        let mut simulation = Simulation::new(board);
+
+        let events = simulation.step();
+        assert_eq!(events.cracked, [(coord(1, 1), 1)]);
+        assert!(events.destroyed.is_empty());
+
+        let events = simulation.step();
+        assert!(events.cracked.is_empty());
+        assert_eq!(events.destroyed, [(coord(1, 1), Item::HardRock)]);
+        assert!(simulation.is_finished());
+        assert!(simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn standing_hard_rock_fails_level() {
+        let mut level = level(
+            &[
+                (coord(0, 0), Item::BombSmall),
+                (coord(1, 1), Item::HardRock),
+            ],
+            &[coord(0, 0)],
+        );
+        level.hard_rock_hits = 3;
+        let board = Board::new(&level, [(coord(1, 0), Item::BombSmall)]);
+        let mut simulation = Simulation::new(board);
+
+        let events = simulation.run();
+        assert_eq!(events[0].cracked, [(coord(1, 1), 2)]);
+        assert_eq!(events[1].cracked, [(coord(1, 1), 1)]);
+        assert!(events.iter().all(|events| events.destroyed.is_empty()));
+        assert!(!simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn timed_bomb_explodes_after_its_fuse() {
+        let mut level = level(
+            &[(coord(0, 0), Item::BombSmall), (coord(2, 1), Item::Rock)],
+            &[coord(0, 0)],
+        );
+        level.fuse_ticks = 3;
+        let board = Board::new(&level, [(coord(1, 0), Item::BombTimed)]);
+        let mut simulation = Simulation::new(board);
+
+        let events = simulation.run();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].ignited, [(coord(1, 0), Item::BombTimed)]);
+        assert_eq!(events[0].fuses, [(coord(1, 0), 3)]);
+        assert_eq!(events[1].fuses, [(coord(1, 0), 2)]);
+        assert_eq!(events[2].fuses, [(coord(1, 0), 1)]);
+        assert!(events[1].exploded.is_empty() && events[2].exploded.is_empty());
+
+        assert_eq!(events[3].tick, 4);
+        assert_eq!(events[3].exploded, [(coord(1, 0), Item::BombTimed)]);
+        assert_eq!(events[3].destroyed, [(coord(2, 1), Item::Rock)]);
+        assert!(events[3].fuses.is_empty());
+        assert!(simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn chasing_enemy_steals_jewel_from_slow_chain() {
+        let enemy_level = |placements: &[(GridCoord, Item)], fire: GridCoord| {
+            let mut objects = vec![
+                (coord(2, 2), Item::Enemy),
+                (coord(2, 4), Item::Jewel),
+                (coord(4, 0), Item::Jewel),
+            ];
+            objects.extend(placements);
+            let mut level = level(&objects, &[fire]);
+            level.enemy_behavior = EnemyBehavior::ChaseJewel;
+            level
+        };
+
+        // the fire's bomb catches the enemy before it moves
+        let level = enemy_level(&[(coord(1, 2), Item::BombSmall)], coord(1, 2));
+        let mut simulation = Simulation::new(Board::new(&level, []));
+        let events = simulation.run();
+        assert_eq!(events[0].destroyed, [(coord(2, 2), Item::Enemy)]);
+        assert!(events[0].moved.is_empty());
+        assert!(simulation.outcome().is_cleared);
+
+        // the timed bomb would catch it, but the enemy reaches the nearer jewel first
+        let level = enemy_level(&[(coord(0, 0), Item::BombSmall)], coord(0, 0));
+        let board = Board::new(&level, [(coord(1, 1), Item::BombTimed)]);
+        let mut simulation = Simulation::new(board);
+        let events = simulation.run();
+        assert_eq!(events[0].moved, [(coord(2, 2), coord(2, 3))]);
+        assert!(events[0].stolen.is_empty());
+        assert_eq!(events[1].moved, [(coord(2, 3), coord(2, 4))]);
+        assert_eq!(events[1].stolen, [coord(2, 4)]);
+        assert!(!simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn absorbing_void_shields_cells_behind_it() {
+        let mut level = level(
+            &[
+                (coord(2, 2), Item::BombMedium),
+                (coord(3, 2), Item::Null),
+                (coord(4, 2), Item::Rock),
+            ],
+            &[coord(2, 2)],
+        );
+        level.voids = VoidBehavior::AbsorbBlasts;
+        let board = Board::new(&level, []);
+        let area = impact_area(&board, coord(2, 2), Item::BombMedium);
+        assert!(!area.contains(&coord(3, 2)));
+        assert!(!area.contains(&coord(4, 2)));
+        assert_eq!(area.len(), 11);
+        // a line blast stops at the void too
+        assert_eq!(
+            impact_area(&board, coord(0, 2), Item::BombHorizontal),
+            [coord(0, 2), coord(1, 2), coord(2, 2)]
+        );
+
+        let mut simulation = Simulation::new(board);
+        simulation.run();
+        assert!(!simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn passing_void_lets_blasts_through() {
+        let mut level = level(
+            &[
+                (coord(2, 2), Item::BombMedium),
+                (coord(3, 2), Item::Null),
+                (coord(4, 2), Item::Rock),
+            ],
+            &[coord(2, 2)],
+        );
+        level.voids = VoidBehavior::PassBlasts;
+        let board = Board::new(&level, []);
+        let area = impact_area(&board, coord(2, 2), Item::BombMedium);
+        assert!(!area.contains(&coord(3, 2)));
+        assert!(area.contains(&coord(4, 2)));
+        assert_eq!(area.len(), 12);
+        assert_eq!(
+            impact_area(&board, coord(0, 2), Item::BombHorizontal),
+            [coord(0, 2), coord(1, 2), coord(2, 2), coord(4, 2)]
+        );
+
+        let mut simulation = Simulation::new(board);
+        let events = simulation.run();
+        assert_eq!(events[0].destroyed, [(coord(4, 2), Item::Rock)]);
+        assert!(simulation.outcome().is_cleared);
+    }
+
+    #[test]
+    fn wall_stops_line_blast() {
+        let level = level(
+            &[
+                (coord(0, 2), Item::BombHorizontal),
+                (coord(2, 2), Item::Wall),
+                (coord(3, 2), Item::Rock),
+            ],
+            &[coord(0, 2)],
+        );
+        let mut simulation = Simulation::new(Board::new(&level, []));
+        assert_eq!(
+            impact_area(simulation.board(), coord(0, 2), Item::BombHorizontal),
+            [coord(0, 2), coord(1, 2), coord(2, 2)]
+        );
+
+        let events = simulation.run();
+        // the wall is hit but stands, and the rock behind it is out of reach
+        assert!(events[0].affected.contains(&(coord(2, 2), 1)));
+        assert!(events[0].destroyed.is_empty());
+        assert!(!simulation.outcome().is_cleared);
+    }
+}
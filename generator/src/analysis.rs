@@ -0,0 +1,79 @@
+use anyhow::Error;
+use bombombo_core::{Item, LevelLayout};
+
+use crate::solver::{LevelSolution, LpSolver, Objective};
+
+/// How many ways a level can be solved with the fewest bombs, and how hard the easiest of
+/// those ways is to find.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    /// Number of distinct placements found.
+    pub solution_count: usize,
+    /// Whether more placements exist than the enumeration was allowed to list.
+    pub is_capped: bool,
+    /// Bombs the player has to place.
+    pub min_bombs: usize,
+    /// Fewest ticks with an explosion among the placements found.
+    pub chain_depth: usize,
+    /// Fewest distinct bomb types among the placements found, a directional bomb counting once
+    /// whichever way it faces.
+    pub bomb_variety: usize,
+}
+
+impl Analysis {
+    /// Enumerates the placements using at most `slack` bombs more than the optimum, up to
+    /// `limit` of them.
+    pub fn new(level: &LevelLayout, slack: usize, limit: usize) -> Result<Self, Error> {
+        let (solutions, is_capped) =
+            LpSolver.enumerate_solutions(level, Objective::MinimalBombs, slack, limit)?;
+
+        // players stop at the first answer they find, so the easiest one rates the level
+        Ok(Self {
+            solution_count: solutions.len(),
+            is_capped,
+            min_bombs: solutions[0].bombs.len(),
+            chain_depth: solutions.iter().map(chain_depth).min().unwrap_or_default(),
+            bomb_variety: solutions.iter().map(bomb_variety).min().unwrap_or_default(),
+        })
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.solution_count == 1
+    }
+
+    /// A rough difficulty rating, 0 for a level cleared by lighting the fire.
+    ///
+    /// Every bomb to place and every bomb type to mix adds a point, every tick of the chain
+    /// half a point, and a unique answer up to two more since it can't be stumbled upon.
+    pub fn difficulty(&self) -> f64 {
+        if self.min_bombs == 0 {
+            return 0.0;
+        }
+
+        self.min_bombs as f64
+            + self.bomb_variety as f64
+            + self.chain_depth as f64 * 0.5
+            + 2.0 / self.solution_count as f64
+    }
+}
+
+/// Replays the placement with the game's rules and counts the ticks where something explodes.
+fn chain_depth(solution: &LevelSolution) -> usize {
+    solution
+        .simulation()
+        .run()
+        .iter()
+        .filter(|events| !events.exploded.is_empty())
+        .count()
+}
+
+/// Counts the bomb types of the placement, the way the inventory counts them.
+fn bomb_variety(solution: &LevelSolution) -> usize {
+    let mut kinds: Vec<Item> = Vec::new();
+    for bomb in solution.bombs.values() {
+        if !kinds.iter().any(|kind| kind.same_kind(bomb)) {
+            kinds.push(*bomb);
+        }
+    }
+    kinds.len()
+}
@@ -0,0 +1,39 @@
+use bombombo_core::{GridCoord, LevelLayout};
+
+use crate::solver::LevelSolution;
+
+pub fn display_level(level: &LevelLayout) {
+    println!("Area: {} x {}:", level.board_size.0, level.board_size.1);
+    display_objects(level);
+    println!("Fire at: {:?}", level.fire_coords);
+}
+
+pub fn display_solution(solution: &LevelSolution) {
+    let LevelSolution {
+        mut level,
+        bombs,
+        is_affected: _,
+    } = solution.clone();
+
+    level.objects.extend(bombs);
+
+    display_objects(&level);
+}
+
+fn display_objects(level: &LevelLayout) {
+    let (width, height) = level.board_size;
+    for y in 0..height {
+        for x in 0..width {
+            let pos = GridCoord { x, y };
+            let letter = level.objects.get(&pos).map_or('.', |item| item.letter());
+
+            let fire = if level.fire_coords.contains(&pos) {
+                "*"
+            } else {
+                ""
+            };
+            print!(" {}{}", letter, fire);
+        }
+        println!();
+    }
+}
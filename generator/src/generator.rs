@@ -0,0 +1,175 @@
+use bombombo_core::{
+    Board, EnemyBehavior, Facing, GridCoord, InventorySlot, Item, LevelLayout, LevelMetaData,
+    Topology, VoidBehavior,
+};
+use rand::prelude::*;
+use rand_chacha::ChaCha20Rng;
+use std::collections::HashMap;
+
+/// Knobs controlling what kind of levels the generator produces.
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    /// Ratio of cells occupied by rocks and jewels.
+    pub density: f64,
+    /// Bomb types the generator may place on the board. The player gets the same types.
+    pub bombs: Vec<Item>,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            density: 0.125,
+            bombs: vec![
+                Item::BombSmall,
+                Item::BombMedium,
+                Item::BombHorizontal,
+                Item::BombVertical,
+            ],
+        }
+    }
+}
+
+pub struct Generator {
+    seed: u64,
+    rng: ChaCha20Rng,
+    config: GeneratorConfig,
+}
+
+impl Generator {
+    pub fn new(seed: u64, config: GeneratorConfig) -> Self {
+        Self {
+            seed,
+            rng: ChaCha20Rng::seed_from_u64(seed),
+            config,
+        }
+    }
+
+    pub fn generate_random_level(&mut self, width: u8, height: u8) -> Result<LevelLayout, ()> {
+        let mut level = LevelLayout {
+            board_size: (width, height),
+            objects: HashMap::new(),
+            fire_coords: Vec::new(),
+            inventory: self.inventory(),
+            enemy_behavior: EnemyBehavior::Idle,
+            voids: VoidBehavior::PassBlasts,
+            fuse_ticks: LevelLayout::DEFAULT_FUSE_TICKS,
+            hard_rock_hits: LevelLayout::DEFAULT_HARD_ROCK_HITS,
+            topology: Topology::Bounded,
+            meta: LevelMetaData {
+                name: format!("Generated {}", self.seed),
+                min_bombs: 0,
+                min_affected_cells: 0,
+            },
+        };
+
+        let cell_count = width as usize * height as usize;
+        let object_num = ((cell_count as f64 * self.config.density) as usize
+            + self.rng.random_range(0..5))
+        .saturating_sub(self.rng.random_range(0..5))
+        .clamp(4, (cell_count / 2).max(4));
+        self.genrate_objects(&mut level, object_num);
+
+        let bomb_num = self.rng.random_range(1..4);
+        self.generate_bombs(&mut level, bomb_num);
+
+        let bomb_positions: Vec<_> = level
+            .objects
+            .iter()
+            .filter(|(_, item)| item.is_bomb())
+            .map(|(&pos, _)| pos)
+            .collect();
+
+        if bomb_positions.is_empty() {
+            return Err(());
+        }
+
+        level.fire_coords = vec![*bomb_positions.choose(&mut self.rng).unwrap()];
+
+        Ok(level)
+    }
+
+    /// Unlimited bombs of the configured types, left out when they match the default inventory.
+    fn inventory(&self) -> Option<Vec<InventorySlot>> {
+        let inventory: Vec<_> = self
+            .config
+            .bombs
+            .iter()
+            .map(|&item| InventorySlot { item, count: None })
+            .collect();
+        (inventory != LevelLayout::DEFAULT_INVENTORY).then_some(inventory)
+    }
+
+    fn genrate_objects(&mut self, level: &mut LevelLayout, object_num: usize) {
+        for _ in 0..object_num {
+            let positions_candidate: Vec<_> = level
+                .cells()
+                .filter(|pos| !level.objects.contains_key(pos))
+                .collect();
+
+            let Some(&pos) = positions_candidate.choose(&mut self.rng) else {
+                break;
+            };
+
+            let item = if self.rng.random_bool(0.6) {
+                Item::Rock
+            } else {
+                Item::Jewel
+            };
+
+            level.objects.insert(pos, item);
+        }
+    }
+
+    fn generate_bombs(&mut self, level: &mut LevelLayout, bomb_num: usize) {
+        if self.config.bombs.is_empty() {
+            return;
+        }
+
+        for _ in 0..bomb_num {
+            let board = Board::new(level, []);
+            for _retry_count in 0..100 {
+                let &item = self
+                    .config
+                    .bombs
+                    .choose_weighted(&mut self.rng, |&item| bomb_weight(item))
+                    .unwrap();
+                // the inventory lists directional bombs once, placed ones face anywhere
+                let item = match item {
+                    Item::BombCone(_) => {
+                        Item::BombCone(*Facing::ALL.choose(&mut self.rng).unwrap())
+                    }
+                    item => item,
+                };
+
+                let possible_positions = level
+                    .cells()
+                    .filter(|&pos| check_possibly_placed(&board, pos, item))
+                    .collect::<Vec<_>>();
+
+                if possible_positions.is_empty() {
+                    continue;
+                } else {
+                    let &pos = possible_positions.choose(&mut self.rng).unwrap();
+                    level.objects.insert(pos, item);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Small bombs show up the most, large, line, timed and cone bombs the least.
+fn bomb_weight(item: Item) -> u32 {
+    match item {
+        Item::BombSmall => 3,
+        Item::BombMedium => 2,
+        _ => 1,
+    }
+}
+
+fn check_possibly_placed(board: &Board, pos: GridCoord, item: Item) -> bool {
+    !board.objects().contains_key(&pos)
+        && !board
+            .impact_area(pos, item)
+            .any(|affected| board.objects().get(&affected) == Some(&Item::Jewel))
+}
@@ -0,0 +1,49 @@
+use anyhow::Error;
+use bombombo_core::LevelLayout;
+use clap::ValueEnum;
+use std::path::Path;
+
+/// The formats the game reads level files in, told apart by their extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LevelFormat {
+    /// `.ron`, the format of the shipped levels.
+    Ron,
+    /// `.grid`, the board drawn with one letter per cell.
+    Grid,
+}
+
+impl LevelFormat {
+    pub fn of(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "grid")
+        {
+            LevelFormat::Grid
+        } else {
+            LevelFormat::Ron
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            LevelFormat::Ron => "ron",
+            LevelFormat::Grid => "grid",
+        }
+    }
+
+    pub fn write(self, level: &LevelLayout) -> Result<String, Error> {
+        Ok(match self {
+            LevelFormat::Ron => level.to_ron()?,
+            LevelFormat::Grid => level.to_grid_text(),
+        })
+    }
+}
+
+/// Reads a level file in the format its extension names.
+pub fn read_level(path: &Path) -> Result<LevelLayout, Error> {
+    let bytes = std::fs::read(path)?;
+    Ok(match LevelFormat::of(path) {
+        LevelFormat::Ron => ron::de::from_bytes(&bytes)?,
+        LevelFormat::Grid => LevelLayout::from_grid_text(str::from_utf8(&bytes)?)?,
+    })
+}
@@ -1,854 +1,549 @@
+mod analysis;
+mod display;
+mod generator;
+mod level_file;
+mod output;
+mod solver;
+mod validation;
+
 use anyhow::Error;
-use good_lp::{
-    Expression, IntoAffineExpression, ProblemVariables, Solution, SolutionStatus, SolverModel,
-    Variable, default_solver, variable::variable, variables,
+use bombombo_core::{Facing, Item, LevelLayout, LevelManifest};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{MultiProgress, ProgressBar};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    analysis::Analysis,
+    display::{display_level, display_solution},
+    generator::{Generator, GeneratorConfig},
+    level_file::{LevelFormat, read_level},
+    output::write_level,
+    solver::{LevelSolution, LpSolver, SolvedLevel},
+    validation::validate_level,
 };
-use indicatif::{MultiProgress, ProgressIterator};
-use rand::prelude::*;
-use rand_chacha::ChaCha20Rng;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-fn main() {
-    // run_solver();
-    compare_two_solver();
-}
-
-fn run_solver() {
-    let seed = 42; // Example seed, can be any u64 value
-    let mut generator = Generator::new(seed);
-
-    let width = 8; // Example width
-    let height = 8; // Example height
 
-    for i in 0..10 {
-        let Ok(level) = generator.generate_random_level(width, height) else {
-            println!("Failed to generate level");
-            continue;
-        };
+/// Level generator and solver for BomBomBo.
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-        println!("# Level {i}");
-        display_level(&level);
+#[derive(Subcommand)]
+enum Command {
+    /// Generate levels of one board size and write them as level files.
+    Generate(GenerateArgs),
+    /// Solve existing level files and print their optimal placements.
+    Solve(SolveArgs),
+    /// Compare minimal-bomb and minimal-affected-cell solutions of random levels.
+    Compare(CompareArgs),
+    /// Generate levels for a range of board sizes and write them as level files.
+    Batch(BatchArgs),
+    /// Count the minimal-bomb solutions of level files and rate their difficulty.
+    Analyze(AnalyzeArgs),
+    /// Check that levels can be cleared, that their mission targets are optimal and that they
+    /// survive a share code.
+    Validate(ValidateArgs),
+    /// Convert level files between RON and grid text, writing each next to its input.
+    Convert(ConvertArgs),
+}
 
-        let lp_solver = LpSolver;
-        let Ok(solution) = lp_solver.solve_minimal_bombs(&level) else {
-            println!("Failed to solve level");
-            continue;
-        };
+#[derive(Args)]
+struct GeneratorArgs {
+    /// Seed of the random number generator.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Ratio of cells occupied by rocks and jewels.
+    #[arg(long, default_value_t = GeneratorConfig::default().density)]
+    density: f64,
+    /// Bomb types the generator may place, separated by commas.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [BombKind::Small, BombKind::Medium, BombKind::Horizontal, BombKind::Vertical],
+    )]
+    bombs: Vec<BombKind>,
+}
 
-        println!("Solution found:");
-        println!("-  Bombs placed: {:?}", solution.bombs);
-        display_solution(&solution);
+impl GeneratorArgs {
+    fn generator(&self) -> Generator {
+        Generator::new(
+            self.seed,
+            GeneratorConfig {
+                density: self.density,
+                bombs: self.bombs.iter().map(|&kind| kind.into()).collect(),
+            },
+        )
     }
 }
 
-fn compare_two_solver() {
-    let seed = 42; // Example seed, can be any u64 value
-    let mut generator = Generator::new(seed);
-
-    const ITER_COUNT: usize = 50;
-
-    let m = MultiProgress::new();
-    let pb_outer = m.add(indicatif::ProgressBar::new(4));
+#[derive(Args)]
+struct OutputArgs {
+    /// Directory the level files are written to.
+    #[arg(long, default_value = "assets/levels")]
+    out_dir: PathBuf,
+    /// Also write the placements reaching both missions to `level_XX.solution.ron`.
+    #[arg(long)]
+    with_solution: bool,
+}
 
-    for aside in 5..8 {
-        let pb_inner = m.add(indicatif::ProgressBar::new(ITER_COUNT as u64));
-        pb_outer.inc(1);
-        for i in 0..ITER_COUNT {
-            pb_inner.inc(1);
-            let Ok(level) = generator.generate_random_level(aside, aside) else {
-                println!("Failed to generate level");
-                continue;
-            };
+/// Filters rejecting trivial or ambiguous levels. Setting any of them runs the analysis on
+/// every generated level, which takes one extra solve per solution.
+#[derive(Args)]
+struct FilterArgs {
+    /// Reject levels with more minimal-bomb solutions than this (1 keeps unique ones only).
+    #[arg(long)]
+    max_solutions: Option<usize>,
+    /// Reject levels rated easier than this.
+    #[arg(long)]
+    min_difficulty: Option<f64>,
+    /// Reject levels rated harder than this.
+    #[arg(long)]
+    max_difficulty: Option<f64>,
+}
 
-            let lp_solver = LpSolver;
-            let Ok(solution1) = lp_solver.solve_minimal_bombs(&level) else {
-                println!("Failed to solve level");
-                continue;
-            };
+impl FilterArgs {
+    fn is_enabled(&self) -> bool {
+        self.max_solutions.is_some()
+            || self.min_difficulty.is_some()
+            || self.max_difficulty.is_some()
+    }
 
-            let Ok(solution2) = lp_solver.solve_minimal_affected_areas(&level) else {
-                println!("Failed to solve level");
-                continue;
-            };
+    fn accepts(&self, level: &LevelLayout) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
 
-            if solution1.bombs.len() >= solution2.bombs.len() {
-                println!(
-                    "Both solutions have the same number of bombs: {}",
-                    solution1.bombs.len()
-                );
-                continue;
-            }
+        // listing as many as allowed tells whether there are more
+        let limit = self.max_solutions.map_or(ANALYSIS_LIMIT, |max| max.max(1));
+        let Ok(analysis) = Analysis::new(level, 0, limit) else {
+            return false;
+        };
+        let difficulty = analysis.difficulty();
 
-            if solution1.count_affected_cells() <= solution2.count_affected_cells() {
-                println!(
-                    "Both solutions have the same number of affected areas: {}",
-                    solution1.count_affected_cells()
-                );
-                // continue;
-            } else {
-                println!("*** DIFFERENT SOLUTIONS FOUND ***");
-            }
+        self.max_solutions
+            .is_none_or(|max| analysis.solution_count <= max && !analysis.is_capped)
+            && self.min_difficulty.is_none_or(|min| difficulty >= min)
+            && self.max_difficulty.is_none_or(|max| difficulty <= max)
+    }
+}
 
-            println!("# Level {i}");
-            display_level(&level);
+#[derive(Args)]
+struct GenerateArgs {
+    #[command(flatten)]
+    generator: GeneratorArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    filter: FilterArgs,
+    #[arg(long, default_value_t = 6)]
+    width: u8,
+    #[arg(long, default_value_t = 6)]
+    height: u8,
+    /// Number of levels to write.
+    #[arg(long, default_value_t = 1)]
+    count: usize,
+}
 
-            println!("Min Bombs Solution:");
-            println!(
-                "Bombs: {}, Affected Areas: {}",
-                solution1.bombs.len(),
-                solution1.count_affected_cells()
-            );
-            display_solution(&solution1);
+#[derive(Args)]
+struct SolveArgs {
+    /// Level files to solve.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
 
-            println!("Min Affected Areas Solution:");
-            println!(
-                "Bombs: {}, Affected Areas: {}",
-                solution2.bombs.len(),
-                solution2.count_affected_cells()
-            );
-            display_solution(&solution2);
+#[derive(Args)]
+struct CompareArgs {
+    #[command(flatten)]
+    generator: GeneratorArgs,
+    /// Smallest side of the square boards.
+    #[arg(long, default_value_t = 5)]
+    min_size: u8,
+    /// Largest side of the square boards.
+    #[arg(long, default_value_t = 7)]
+    max_size: u8,
+    /// Number of levels compared per board size.
+    #[arg(long, default_value_t = 50)]
+    iterations: usize,
+}
 
-            let level_str = ron::ser::to_string(&level).unwrap();
-            println!("Level RON: {}", level_str);
-        }
-        pb_inner.finish();
-    }
+#[derive(Args)]
+struct BatchArgs {
+    #[command(flatten)]
+    generator: GeneratorArgs,
+    #[command(flatten)]
+    output: OutputArgs,
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Smallest side of the square boards.
+    #[arg(long, default_value_t = 5)]
+    min_size: u8,
+    /// Largest side of the square boards.
+    #[arg(long, default_value_t = 7)]
+    max_size: u8,
+    /// Number of levels written per board size.
+    #[arg(long, default_value_t = 10)]
+    per_size: usize,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
-pub enum Item {
-    BombSmall,
-    BombMedium,
-    BombLarge,
-    BombHorizontal,
-    BombVertical,
-    Null,
-    Rock,
-    Jewel,
-    Eraser,
-    Enemy,
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// Level files to analyze.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Also count placements using up to this many bombs more than the optimum.
+    #[arg(long, default_value_t = 0)]
+    slack: usize,
+    /// Stop counting after this many solutions.
+    #[arg(long, default_value_t = ANALYSIS_LIMIT)]
+    limit: usize,
 }
 
-impl Item {
-    pub fn is_bomb(&self) -> bool {
-        matches!(
-            self,
-            Item::BombSmall
-                | Item::BombMedium
-                | Item::BombLarge
-                | Item::BombHorizontal
-                | Item::BombVertical
-        )
-    }
+#[derive(Args)]
+struct ValidateArgs {
+    /// Level files to check. Defaults to every level listed in `--manifest`.
+    paths: Vec<PathBuf>,
+    /// Manifest listing the levels of the game.
+    #[arg(long, default_value = "assets/levels/manifest.ron")]
+    manifest: PathBuf,
+}
 
-    pub const fn to_sprite_index(self) -> usize {
-        match self {
-            Item::BombSmall => 0,
-            Item::BombMedium => 1,
-            Item::BombLarge => 2,
-            Item::BombHorizontal => 3,
-            Item::BombVertical => 4,
-            Item::Null => 7,
-            Item::Rock => 8,
-            Item::Jewel => 10,
-            Item::Enemy => 11,
-            Item::Eraser => 12,
-        }
-    }
+#[derive(Args)]
+struct ConvertArgs {
+    /// Level files to convert.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Format to convert to.
+    #[arg(long, value_enum)]
+    to: LevelFormat,
+    /// Overwrite converted files that already exist.
+    #[arg(long)]
+    force: bool,
 }
 
-impl Item {
-    pub fn impact_zone(&self) -> &'static [(i8, i8)] {
-        match self {
-            // . . . . .
-            // . x x x .
-            // . x # x .
-            // . x x x.
-            // . . . . .
-            Item::BombSmall => &[
-                (-1, 1),
-                (0, 1),
-                (1, 1),
-                (-1, 0),
-                (0, 0),
-                (1, 0),
-                (-1, -1),
-                (0, -1),
-                (1, -1),
-            ],
-
-            // . . x . .
-            // . x x x .
-            // x x # x x
-            // . x x x .
-            // . . x . .
-            Item::BombMedium => &[
-                (0, 2),
-                (-1, 1),
-                (0, 1),
-                (1, 1),
-                (-2, 0),
-                (-1, 0),
-                (0, 0),
-                (1, 0),
-                (2, 0),
-                (-1, -1),
-                (0, -1),
-                (1, -1),
-                (0, -2),
-            ],
-
-            // . . . x . . .
-            // . . x x x . .
-            // . x x x x x .
-            // x x x # x x x
-            // . x x x x x .
-            // . . x x x . .
-            // . . . x . . .
-            Item::BombLarge => &[
-                (0, 3),
-                (-1, 2),
-                (0, 2),
-                (1, 2),
-                (-2, 1),
-                (-1, 1),
-                (0, 1),
-                (1, 1),
-                (2, 1),
-                (-3, 0),
-                (-2, 0),
-                (-1, 0),
-                (0, 0),
-                (1, 0),
-                (2, 0),
-                (3, 0),
-                (-2, -1),
-                (-1, -1),
-                (0, -1),
-                (1, -1),
-                (2, -1),
-                (-1, -2),
-                (0, -2),
-                (1, -2),
-                (0, -3),
-            ],
-
-            // . . x . .
-            // . . x . .
-            // . . # . .
-            // . . x . .
-            // . . x . .
-            Item::BombVertical => &[
-                (0, 10),
-                (0, 9),
-                (0, 8),
-                (0, 7),
-                (0, 6),
-                (0, 5),
-                (0, 4),
-                (0, 3),
-                (0, 2),
-                (0, 1),
-                (0, 0),
-                (0, -1),
-                (0, -2),
-                (0, -3),
-                (0, -4),
-                (0, -5),
-                (0, -6),
-                (0, -7),
-                (0, -8),
-                (0, -9),
-                (0, -10),
-            ],
-
-            // . . . . .
-            // . . . . .
-            // x x # x x
-            // . . . . .
-            // . . . . .
-            Item::BombHorizontal => &[
-                (10, 0),
-                (9, 0),
-                (8, 0),
-                (7, 0),
-                (6, 0),
-                (5, 0),
-                (4, 0),
-                (3, 0),
-                (2, 0),
-                (1, 0),
-                (0, 0),
-                (-1, 0),
-                (-2, 0),
-                (-3, 0),
-                (-4, 0),
-                (-5, 0),
-                (-6, 0),
-                (-7, 0),
-                (-8, 0),
-                (-9, 0),
-                (-10, 0),
-            ],
-
-            Item::Eraser => &[(0, 0)],
-
-            Item::Rock | Item::Jewel | Item::Enemy | Item::Null => &[],
-        }
-    }
+/// Default cap on the solutions enumerated for a level.
+const ANALYSIS_LIMIT: usize = 20;
+
+/// Generated levels tried per level written before giving up.
+const MAX_ATTEMPTS_PER_LEVEL: usize = 50;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum BombKind {
+    Small,
+    Medium,
+    Large,
+    Horizontal,
+    Vertical,
+    Timed,
+    Cone,
 }
 
-impl From<u8> for Item {
-    fn from(value: u8) -> Self {
-        match value {
-            0 => Item::BombSmall,
-            1 => Item::BombMedium,
-            2 => Item::BombLarge,
-            3 => Item::BombHorizontal,
-            4 => Item::BombVertical,
-            255 => Item::Eraser,
-            _ => panic!("Invalid item index"),
+impl From<BombKind> for Item {
+    fn from(kind: BombKind) -> Self {
+        match kind {
+            BombKind::Small => Item::BombSmall,
+            BombKind::Medium => Item::BombMedium,
+            BombKind::Large => Item::BombLarge,
+            BombKind::Horizontal => Item::BombHorizontal,
+            BombKind::Vertical => Item::BombVertical,
+            BombKind::Timed => Item::BombTimed,
+            BombKind::Cone => Item::BombCone(Facing::Up),
         }
     }
 }
 
-struct Generator {
-    seed: u64,
-    rng: ChaCha20Rng,
+fn main() -> Result<(), Error> {
+    match Cli::parse().command {
+        Command::Generate(args) => generate(args),
+        Command::Solve(args) => solve(args),
+        Command::Compare(args) => compare_two_solver(args),
+        Command::Batch(args) => batch(args),
+        Command::Analyze(args) => analyze(args),
+        Command::Validate(args) => validate(args),
+        Command::Convert(args) => convert(args),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Level {
-    width: usize,
-    height: usize,
-    objects: HashMap<(usize, usize), Item>,
-    fire: (usize, usize),
-}
+fn generate(args: GenerateArgs) -> Result<(), Error> {
+    let mut generator = args.generator.generator();
+    let pb = ProgressBar::new(args.count as u64);
 
-impl Generator {
-    fn new(seed: u64) -> Self {
-        Self {
-            seed,
-            rng: ChaCha20Rng::seed_from_u64(seed),
+    let mut written = 0;
+    // give up on board sizes that keep producing unsolvable or rejected levels
+    for _attempt in 0..args.count * MAX_ATTEMPTS_PER_LEVEL {
+        if written == args.count {
+            break;
         }
+        let Some(solved) =
+            generate_solved_level(&mut generator, &args.filter, args.width, args.height)
+        else {
+            continue;
+        };
+        let path = write_level(&args.output.out_dir, &solved, args.output.with_solution)?;
+        pb.println(format!("Wrote {}", path.display()));
+        pb.inc(1);
+        written += 1;
     }
+    pb.finish();
 
-    fn generate_random_level(&mut self, width: usize, height: usize) -> Result<Level, ()> {
-        let mut objects = HashMap::new();
-
-        let object_num = ((width * height) / 8 + self.rng.random_range(0..5))
-            .saturating_sub(self.rng.random_range(0..5))
-            .clamp(4, 12);
-        self.genrate_objects(&mut objects, object_num, (width, height));
-
-        let bomb_num = self.rng.random_range(1..4);
-        self.generate_bombs(&mut objects, bomb_num, (width, height));
-
-        let bomb_positions: Vec<_> = objects
-            .iter()
-            .filter(|(_, item)| item.is_bomb())
-            .map(|(&pos, _)| pos)
-            .collect();
+    if written < args.count {
+        println!("Only {written} of {} levels could be generated", args.count);
+    }
+    Ok(())
+}
 
-        if bomb_positions.is_empty() {
-            return Err(());
-        }
+fn solve(args: SolveArgs) -> Result<(), Error> {
+    let lp_solver = LpSolver;
 
-        let &fire = bomb_positions.choose(&mut self.rng).unwrap();
+    for path in &args.paths {
+        let level = read_level(path)?;
 
-        Ok(Level {
-            width,
-            height,
-            objects,
-            fire,
-        })
-    }
-
-    fn genrate_objects(
-        &mut self,
-        objects: &mut HashMap<(usize, usize), Item>,
-        object_num: usize,
-        (width, height): (usize, usize),
-    ) {
-        for _ in 0..object_num {
-            let positions_candidate: Vec<_> = (0..width)
-                .flat_map(|x| (0..height).map(move |y| (x, y)))
-                .filter(|pos| !objects.contains_key(pos))
-                .collect();
-
-            let &(x, y) = positions_candidate.choose(&mut self.rng).unwrap();
-
-            let item = if self.rng.random_bool(0.6) {
-                Item::Rock
-            } else {
-                Item::Jewel
-            };
+        println!("# {} ({})", level.meta.name, path.display());
+        display_level(&level);
 
-            objects.insert((x, y), item);
+        match lp_solver.solve_minimal_bombs(&level) {
+            Ok(solution) => {
+                println!(
+                    "Min Bombs Solution: {} (meta: {})",
+                    solution.bombs.len(),
+                    level.meta.min_bombs
+                );
+                warn_if_not_cleared(&solution);
+                display_solution(&solution);
+            }
+            Err(err) => println!("Failed to solve level: {err}"),
         }
-    }
 
-    fn generate_bombs(
-        &mut self,
-        objects: &mut HashMap<(usize, usize), Item>,
-        bomb_num: usize,
-        (width, height): (usize, usize),
-    ) {
-        for _ in 0..bomb_num {
-            for _retry_count in 0..100 {
-                let item = match self.rng.random_range(0..7) {
-                    0..3 => Item::BombSmall,
-                    3..5 => Item::BombMedium,
-                    5 => Item::BombHorizontal,
-                    _ => Item::BombVertical,
-                };
-
-                let possible_positions = (0..width)
-                    .flat_map(|x| (0..height).map(move |y| (x, y)))
-                    .filter(|pos| check_possibly_placed(pos, item, objects))
-                    .collect::<Vec<_>>();
-
-                if possible_positions.is_empty() {
-                    continue;
-                } else {
-                    let &(x, y) = possible_positions.choose(&mut self.rng).unwrap();
-                    objects.insert((x, y), item);
-                    break;
-                }
+        match lp_solver.solve_minimal_affected_areas(&level) {
+            Ok(solution) => {
+                println!(
+                    "Min Affected Areas Solution: {} (meta: {})",
+                    solution.count_affected_cells(),
+                    level.meta.min_affected_cells
+                );
+                warn_if_not_cleared(&solution);
+                display_solution(&solution);
             }
+            Err(err) => println!("Failed to solve level: {err}"),
         }
     }
+    Ok(())
 }
 
-struct LpSolver;
-
-struct Variables {
-    is_placed: HashMap<(usize, usize, usize, Item), Variable>,
-    is_affected: HashMap<(usize, usize), Variable>,
+/// The solver doesn't see enemies move, so its placements are checked in the game's rules.
+fn warn_if_not_cleared(solution: &LevelSolution) {
+    if !solution.clears_level() {
+        println!("Warning: this placement does not clear the level in the game");
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct LevelSolution {
-    level: Level,
-    bombs: HashMap<(usize, usize), Item>,
-    is_affected: Vec<Vec<bool>>,
-}
+fn analyze(args: AnalyzeArgs) -> Result<(), Error> {
+    for path in &args.paths {
+        let level = read_level(path)?;
 
-impl LevelSolution {
-    fn count_affected_cells(&self) -> usize {
-        self.is_affected.iter().flatten().filter(|&&x| x).count()
+        println!("# {} ({})", level.meta.name, path.display());
+        match Analysis::new(&level, args.slack, args.limit) {
+            Ok(analysis) => {
+                let more = if analysis.is_capped { "+" } else { "" };
+                println!(
+                    "Solutions: {}{more}{}",
+                    analysis.solution_count,
+                    if analysis.is_unique() {
+                        " (unique)"
+                    } else {
+                        ""
+                    }
+                );
+                println!(
+                    "Bombs: {}, Chain Depth: {}, Bomb Variety: {}",
+                    analysis.min_bombs, analysis.chain_depth, analysis.bomb_variety
+                );
+                println!("Difficulty: {:.1}", analysis.difficulty());
+            }
+            Err(err) => println!("Failed to solve level: {err}"),
+        }
     }
+    Ok(())
 }
 
-impl LpSolver {
-    const MAX_DEPTH: usize = 10; // Maximum depth for the search
-
-    const BOMBS: [Item; 4] = [
-        Item::BombSmall,
-        Item::BombMedium,
-        Item::BombHorizontal,
-        Item::BombVertical,
-    ];
-
-    fn solve_minimal_bombs(&self, level: &Level) -> Result<LevelSolution, Error> {
-        let (vars, variables) = self.define_variables(level);
-
-        let objective = self.define_objective_function_minimum_bombs(level, &variables);
-
-        let mut problem = vars.minimise(objective).using(default_solver);
-        problem.set_parameter("log", "0");
-
-        self.define_constraints(&mut problem, level, &variables);
-
-        let solution = problem.solve()?;
+fn validate(args: ValidateArgs) -> Result<(), Error> {
+    let paths = if args.paths.is_empty() {
+        let manifest: LevelManifest = ron::de::from_bytes(&std::fs::read(&args.manifest)?)?;
+        // level paths are relative to the manifest
+        let levels_dir = args.manifest.parent().unwrap_or(Path::new(""));
+        manifest
+            .level_paths()
+            .map(|path| levels_dir.join(path))
+            .collect()
+    } else {
+        args.paths
+    };
+
+    let mut failed = 0;
+    for path in &paths {
+        let problems = match read_level(path) {
+            Ok(level) => validate_level(&level)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            Err(err) => vec![format!("failed to load: {err}")],
+        };
 
-        match solution.status() {
-            SolutionStatus::Optimal => self.build_solution(&solution, level, &variables),
-            _ => Err(Error::msg("No feasible solution found")),
+        if problems.is_empty() {
+            println!("ok    {}", path.display());
+        } else {
+            failed += 1;
+            println!("FAIL  {}", path.display());
+            for problem in problems {
+                println!("      - {problem}");
+            }
         }
     }
 
-    fn solve_minimal_affected_areas(&self, level: &Level) -> Result<LevelSolution, Error> {
-        let (vars, variables) = self.define_variables(level);
-
-        let objective = self.define_objective_function_affected_areas(level, &variables);
-
-        let mut problem = vars.minimise(objective).using(default_solver);
-        problem.set_parameter("log", "0");
-
-        self.define_constraints(&mut problem, level, &variables);
-
-        let solution = problem.solve()?;
-
-        match solution.status() {
-            SolutionStatus::Optimal => self.build_solution(&solution, level, &variables),
-            _ => Err(Error::msg("No feasible solution found")),
-        }
+    if failed > 0 {
+        return Err(Error::msg(format!(
+            "{failed} of {} levels failed validation",
+            paths.len()
+        )));
     }
+    Ok(())
+}
 
-    fn define_variables(&self, level: &Level) -> (ProblemVariables, Variables) {
-        // Define variables for the LP solver
-        let mut vars = variables!();
-
-        let mut is_placed = HashMap::new();
-        for x in 0..level.width {
-            for y in 0..level.height {
-                for depth in 0..Self::MAX_DEPTH {
-                    // Create a variable for each bomb type at each position
-                    // The depth is not used in this example, but can be used for more complex scenarios
-                    for item in Self::BOMBS {
-                        is_placed.insert(
-                            (x, y, depth, item),
-                            vars.add(
-                                variable()
-                                    .binary()
-                                    .name(format!("is_placed_{}_{}_{}_{:?}", x, y, depth, item)),
-                            ),
-                        );
-                    }
-                }
-            }
+fn convert(args: ConvertArgs) -> Result<(), Error> {
+    for path in &args.paths {
+        let out_path = path.with_extension(args.to.extension());
+        if out_path == *path {
+            println!("skip  {} (already {:?})", path.display(), args.to);
+            continue;
         }
-
-        let mut is_affected = HashMap::new();
-        for x in 0..level.width {
-            for y in 0..level.height {
-                is_affected.insert(
-                    (x, y),
-                    vars.add(variable().binary().name(format!("is_affected_{}_{}", x, y))),
-                );
-            }
+        if out_path.exists() && !args.force {
+            return Err(Error::msg(format!(
+                "{} exists, pass --force to overwrite it",
+                out_path.display()
+            )));
         }
 
-        (
-            vars,
-            Variables {
-                is_placed,
-                is_affected,
-            },
-        )
+        let level = read_level(path)?;
+        std::fs::write(&out_path, args.to.write(&level)?)?;
+        println!("wrote {}", out_path.display());
     }
+    Ok(())
+}
 
-    fn define_constraints<S: SolverModel>(
-        &self,
-        problem: &mut S,
-        level: &Level,
-        variables: &Variables,
-    ) {
-        // in each cell, at most one bomb can be placed
-        for (x, y) in (0..level.width).flat_map(|x| (0..level.height).map(move |y| (x, y))) {
-            let lhs = Self::BOMBS
-                .iter()
-                .flat_map(|&item| {
-                    (0..Self::MAX_DEPTH).map(move |depth| variables.is_placed[&(x, y, depth, item)])
-                })
-                .fold(Expression::default(), |acc, var| acc + var);
-            problem.add_constraint(
-                lhs.leq(1.0.into_expression())
-                    .set_name(format!("cell_{}_{}", x, y)),
-            );
-        }
+fn compare_two_solver(args: CompareArgs) -> Result<(), Error> {
+    let mut generator = args.generator.generator();
 
-        // already placed bombs
-        for (&pos, &item) in &level.objects {
-            if item.is_bomb() {
-                if pos == level.fire {
-                    problem.add_constraint(
-                        variables.is_placed[&(pos.0, pos.1, 0, item)]
-                            .into_expression()
-                            .eq(1.0.into_expression())
-                            .set_name(format!("placed_bomb_{}_{}", pos.0, pos.1)),
-                    );
-                } else {
-                    problem.add_constraint(
-                        (1..Self::MAX_DEPTH)
-                            .fold(Expression::default(), |acc, depth| {
-                                acc + variables.is_placed[&(pos.0, pos.1, depth, item)]
-                            })
-                            .eq(1.0.into_expression())
-                            .set_name(format!("placed_bomb_at_{}_{}", pos.0, pos.1)),
-                    );
-                };
-            }
-        }
+    let m = MultiProgress::new();
+    let pb_outer = m.add(ProgressBar::new(
+        args.max_size.saturating_sub(args.min_size) as u64 + 1,
+    ));
 
-        // already placed objects
-        for (pos, &item) in &level.objects {
-            if !item.is_bomb() {
-                problem.add_constraint(
-                    Self::BOMBS
-                        .iter()
-                        .flat_map(|&bomb| {
-                            (0..Self::MAX_DEPTH)
-                                .map(move |depth| variables.is_placed[&(pos.0, pos.1, depth, bomb)])
-                        })
-                        .fold(Expression::default(), |acc, var| acc + var)
-                        .eq(0.0.into_expression())
-                        .set_name(format!("no_bomb_at_{}_{}", pos.0, pos.1)),
-                );
-            }
-        }
+    for aside in args.min_size..=args.max_size {
+        let pb_inner = m.add(ProgressBar::new(args.iterations as u64));
+        pb_outer.inc(1);
+        for i in 0..args.iterations {
+            pb_inner.inc(1);
+            let Ok(level) = generator.generate_random_level(aside, aside) else {
+                println!("Failed to generate level");
+                continue;
+            };
 
-        // bombs affect their impact zones
-        // lower bound
-        for (x, y) in (0..level.width).flat_map(|x| (0..level.height).map(move |y| (x, y))) {
-            for &item in &Self::BOMBS {
-                let bomb_var = (0..Self::MAX_DEPTH).fold(Expression::default(), |acc, depth| {
-                    acc + variables.is_placed[&(x, y, depth, item)]
-                });
-
-                for &(dx, dy) in item.impact_zone() {
-                    let affected_x = (x as i8 + dx) as usize;
-                    let affected_y = (y as i8 + dy) as usize;
-                    if let Some(&affected_var) =
-                        variables.is_affected.get(&(affected_x, affected_y))
-                    {
-                        // If the bomb is placed, the affected cell must be affected
-                        problem.add_constraint(
-                            affected_var
-                                .into_expression()
-                                .geq(bomb_var.clone())
-                                .set_name(format!(
-                                    "{:?}_{}_{}_affects_{}_{}",
-                                    item, x, y, affected_x, affected_y
-                                )),
-                        );
-                    }
-                }
-            }
-        }
+            let lp_solver = LpSolver;
+            let Ok(solution1) = lp_solver.solve_minimal_bombs(&level) else {
+                println!("Failed to solve level");
+                continue;
+            };
 
-        // upper bound
-        for (x, y) in (0..level.width).flat_map(|x| (0..level.height).map(move |y| (x, y))) {
-            let parent_positions: Vec<_> = (0..level.width)
-                .flat_map(|px| (0..level.height).map(move |py| (px, py)))
-                .flat_map(|(px, py)| {
-                    Self::BOMBS
-                        .iter()
-                        .filter(move |&&item| check_is_position_affected((px, py), item, (x, y)))
-                        .map(move |&item| (px, py, item))
-                })
-                .flat_map(|(px, py, item)| {
-                    (0..Self::MAX_DEPTH).map(move |depth| (px, py, depth, item))
-                })
-                .collect();
-
-            problem.add_constraint(
-                variables.is_affected[&(x, y)]
-                    .into_expression()
-                    .leq(parent_positions.iter().fold(
-                        Expression::default(),
-                        |acc, &(px, py, depth, item)| {
-                            acc + variables.is_placed[&(px, py, depth, item)]
-                        },
-                    ))
-                    .set_name(format!("bombs_affecting_{}_{}", x, y)),
-            );
-        }
+            let Ok(solution2) = lp_solver.solve_minimal_affected_areas(&level) else {
+                println!("Failed to solve level");
+                continue;
+            };
 
-        // rocks must be affected
-        for (pos, &item) in &level.objects {
-            if item == Item::Rock {
-                let affected_var = variables.is_affected[pos];
-                problem.add_constraint(
-                    affected_var
-                        .into_expression()
-                        .geq(1.0.into_expression())
-                        .set_name(format!("rock_{}_{}_affected", pos.0, pos.1)),
+            if solution1.bombs.len() >= solution2.bombs.len() {
+                println!(
+                    "Both solutions have the same number of bombs: {}",
+                    solution1.bombs.len()
                 );
+                continue;
             }
-        }
 
-        // jewels must not be affected
-        for (pos, &item) in &level.objects {
-            if item == Item::Jewel {
-                let affected_var = variables.is_affected[pos];
-                problem.add_constraint(
-                    affected_var
-                        .into_expression()
-                        .eq(0.0.into_expression())
-                        .set_name(format!("jewel_{}_{}_not_affected", pos.0, pos.1)),
+            if solution1.count_affected_cells() <= solution2.count_affected_cells() {
+                println!(
+                    "Both solutions have the same number of affected areas: {}",
+                    solution1.count_affected_cells()
                 );
+            } else {
+                println!("*** DIFFERENT SOLUTIONS FOUND ***");
             }
-        }
 
-        // if a bomb is not on fire, there must be at least one bomb affecting it
-        for (x, y, depth) in (0..level.width)
-            .flat_map(|x| (0..level.height).map(move |y| (x, y)))
-            .flat_map(|(x, y)| (0..Self::MAX_DEPTH).map(move |depth| (x, y, depth)))
-        {
-            if (x, y) == level.fire {
-                continue; // Skip the fire position
-            }
+            println!("# Level {i}");
+            display_level(&level);
 
-            // position and items which can affect the position
-            let possible_parents: Vec<(usize, usize, usize, Item)> = (0..level.width)
-                .flat_map(|x| (0..level.height).map(move |y| (x, y)))
-                .filter(|&(px, py)| (px, py) != (x, y))
-                .flat_map(|(px, py)| Self::BOMBS.iter().map(move |&item| (px, py, item)))
-                .filter(|&(px, py, item)| check_is_position_affected((px, py), item, (x, y)))
-                .flat_map(|(px, py, item)| (0..depth).map(move |depth| (px, py, depth, item)))
-                .collect();
-
-            problem.add_constraint(
-                possible_parents
-                    .iter()
-                    .fold(Expression::default(), |acc, &(px, py, pdepth, item)| {
-                        acc + variables.is_placed[&(px, py, pdepth, item)]
-                    })
-                    .geq(
-                        Self::BOMBS
-                            .iter()
-                            .fold(Expression::default(), |acc, &item| {
-                                acc + variables.is_placed[&(x, y, depth, item)]
-                            })
-                            .into_expression(),
-                    )
-                    .set_name(format!("bombs_affecting_{}_{}", x, y)),
+            println!("Min Bombs Solution:");
+            println!(
+                "Bombs: {}, Affected Areas: {}",
+                solution1.bombs.len(),
+                solution1.count_affected_cells()
             );
-        }
-    }
-
-    fn define_objective_function_minimum_bombs(
-        &self,
-        _level: &Level,
-        variables: &Variables,
-    ) -> impl IntoAffineExpression {
-        // Set the objective function for the LP solver
-        // For example, minimize the number of bombs used
-
-        variables
-            .is_placed
-            .values()
-            .fold(Expression::default(), |acc, &var| acc + var)
-    }
+            display_solution(&solution1);
 
-    fn define_objective_function_affected_areas(
-        &self,
-        level: &Level,
-        variables: &Variables,
-    ) -> impl IntoAffineExpression {
-        // Set the objective function to maximize the number of affected areas
-        (0..level.width)
-            .flat_map(|x| (0..level.height).map(move |y| (x, y)))
-            .filter_map(|pos| variables.is_affected.get(&pos))
-            .fold(Expression::default(), |acc, &var| acc + var)
-            * 100.0
-            + self.define_objective_function_minimum_bombs(level, variables)
-    }
+            println!("Min Affected Areas Solution:");
+            println!(
+                "Bombs: {}, Affected Areas: {}",
+                solution2.bombs.len(),
+                solution2.count_affected_cells()
+            );
+            display_solution(&solution2);
 
-    fn build_solution<S: Solution>(
-        &self,
-        solution: &S,
-        level: &Level,
-        variables: &Variables,
-    ) -> Result<LevelSolution, Error> {
-        // Run the LP solver and return the solution
-        // This is a placeholder; actual implementation would involve calling an LP solver library
-        let mut bombs = HashMap::new();
-        for (x, y) in (0..level.width).flat_map(|x| (0..level.height).map(move |y| (x, y))) {
-            for depth in 0..Self::MAX_DEPTH {
-                for &item in &Self::BOMBS {
-                    let var = variables.is_placed[&(x, y, depth, item)];
-                    if solution.value(var) > 0.5 && !level.objects.contains_key(&(x, y)) {
-                        bombs.insert((x, y), item);
-                    }
-                }
-            }
+            println!("Level RON:\n{}", level.to_ron()?);
         }
-
-        let is_affected: Vec<Vec<bool>> = (0..level.height)
-            .map(|y| {
-                (0..level.width)
-                    .map(|x| {
-                        variables
-                            .is_affected
-                            .get(&(x, y))
-                            .map_or(false, |&var| solution.value(var) > 0.5)
-                    })
-                    .collect()
-            })
-            .collect();
-
-        Ok(LevelSolution {
-            level: level.clone(),
-            bombs,
-            is_affected,
-        })
+        pb_inner.finish();
     }
+    Ok(())
 }
 
-fn check_is_position_affected(
-    (px, py): (usize, usize),
-    item: Item,
-    (x, y): (usize, usize),
-) -> bool {
-    item.impact_zone().iter().any(|&(dx, dy)| {
-        let affected_x = (px as i8 + dx) as usize;
-        let affected_y = (py as i8 + dy) as usize;
-        affected_x == x && affected_y == y
-    })
-}
-
-fn check_possibly_placed(
-    pos: &(usize, usize),
-    item: Item,
-    objects: &HashMap<(usize, usize), Item>,
-) -> bool {
-    !objects.contains_key(pos)
-        && !item.impact_zone().iter().any(|(dx, dy)| {
-            let new_x = (pos.0 as i8 + dx) as u8;
-            let new_y = (pos.1 as i8 + dy) as u8;
-            objects
-                .get(&(new_x as usize, new_y as usize))
-                .and_then(|&item| Some(item == Item::Jewel))
-                .unwrap_or(false)
-        })
-}
-
-fn display_level(level: &Level) {
-    println!("Area: {} x {}:", level.width, level.height);
-    display_objects(level);
-    println!("Fire at: {:?}", level.fire);
-}
-
-fn display_solution(solution: &LevelSolution) {
-    let LevelSolution {
-        mut level,
-        bombs,
-        is_affected,
-    } = solution.clone();
+fn batch(args: BatchArgs) -> Result<(), Error> {
+    let mut generator = args.generator.generator();
 
-    for ((x, y), item) in &bombs {
-        level.objects.insert((*x, *y), *item);
-    }
+    let m = MultiProgress::new();
+    let pb_outer = m.add(ProgressBar::new(
+        args.max_size.saturating_sub(args.min_size) as u64 + 1,
+    ));
 
-    display_objects(&level);
-}
+    for aside in args.min_size..=args.max_size {
+        let pb_inner = m.add(ProgressBar::new(args.per_size as u64));
+        pb_outer.inc(1);
 
-fn display_objects(level: &Level) {
-    for y in 0..level.height {
-        for x in 0..level.width {
-            let letter = match level.objects.get(&(x, y)) {
-                Some(item) => match item {
-                    Item::BombSmall => "S",
-                    Item::BombMedium => "M",
-                    Item::BombLarge => "L",
-                    Item::BombHorizontal => "H",
-                    Item::BombVertical => "V",
-                    Item::Null => "x",
-                    Item::Rock => "R",
-                    Item::Jewel => "J",
-                    Item::Eraser => "E",
-                    Item::Enemy => "X",
-                },
-                None => ".",
+        let mut written = 0;
+        for _attempt in 0..args.per_size * MAX_ATTEMPTS_PER_LEVEL {
+            if written == args.per_size {
+                break;
+            }
+            let Some(solved) = generate_solved_level(&mut generator, &args.filter, aside, aside)
+            else {
+                continue;
             };
-
-            let fire = if (x, y) == level.fire { "*" } else { "" };
-            print!(" {}{}", letter, fire);
+            let path = write_level(&args.output.out_dir, &solved, args.output.with_solution)?;
+            m.println(format!("Wrote {}", path.display()))?;
+            pb_inner.inc(1);
+            written += 1;
         }
-        println!();
+        pb_inner.finish();
     }
+    pb_outer.finish();
+    Ok(())
+}
+
+/// Generates a level and fills its mission targets, or `None` if it has no solution or the
+/// filters reject it.
+fn generate_solved_level(
+    generator: &mut Generator,
+    filter: &FilterArgs,
+    width: u8,
+    height: u8,
+) -> Option<SolvedLevel> {
+    let level = generator.generate_random_level(width, height).ok()?;
+    let solved = LpSolver.solve_level(level).ok()?;
+    filter.accepts(&solved.level).then_some(solved)
 }
@@ -0,0 +1,56 @@
+use anyhow::Error;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::solver::{LevelSolution, SolvedLevel};
+
+/// Writes the level to the first unused `level_XX.ron` in `dir`, never overwriting a file.
+///
+/// With `with_solution`, the placements reaching both missions are written next to it as
+/// `level_XX.solution.ron`.
+pub fn write_level(
+    dir: &Path,
+    solved: &SolvedLevel,
+    with_solution: bool,
+) -> Result<PathBuf, Error> {
+    std::fs::create_dir_all(dir)?;
+
+    let (path, solution_path) = (1..)
+        .map(|index| {
+            (
+                dir.join(format!("level_{index:02}.ron")),
+                dir.join(format!("level_{index:02}.solution.ron")),
+            )
+        })
+        .find(|(path, solution_path)| !path.exists() && !solution_path.exists())
+        .unwrap();
+
+    std::fs::write(&path, solved.level.to_ron()?)?;
+    if with_solution {
+        std::fs::write(&solution_path, solution_to_ron(solved)?)?;
+    }
+    Ok(path)
+}
+
+/// Lists the bombs to place for each mission, in the same notation as the level's objects.
+fn solution_to_ron(solved: &SolvedLevel) -> Result<String, Error> {
+    let mut out = String::new();
+    writeln!(out, "(")?;
+    write_placements(&mut out, "min_bombs", &solved.min_bombs)?;
+    write_placements(&mut out, "min_affected_cells", &solved.min_affected_cells)?;
+    writeln!(out, ")")?;
+    Ok(out)
+}
+
+fn write_placements(out: &mut String, name: &str, solution: &LevelSolution) -> Result<(), Error> {
+    writeln!(out, "    {name}: {{")?;
+    let mut bombs: Vec<_> = solution.bombs.iter().collect();
+    bombs.sort_by_key(|(coord, _)| (coord.y, coord.x));
+    for (coord, item) in bombs {
+        writeln!(out, "        {coord}: {},", ron::to_string(item)?)?;
+    }
+    writeln!(out, "    }},")?;
+    Ok(())
+}
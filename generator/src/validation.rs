@@ -0,0 +1,138 @@
+use bombombo_core::{Item, LevelLayout, decode_share_code, encode_share_code};
+use std::fmt;
+
+use crate::solver::{LpSolver, Objective};
+
+/// Something wrong with a level file.
+#[derive(Debug, Clone)]
+pub enum Problem {
+    /// No placement completes the mission.
+    Unsolvable(Objective),
+    /// The solver's placement plays out differently in the game, so the solver can't be
+    /// trusted on this level.
+    RulesMismatch {
+        objective: Objective,
+        solver_score: usize,
+        /// `None` when the placement doesn't clear the level in the game.
+        game_score: Option<usize>,
+    },
+    /// The mission target in the metadata isn't the optimum.
+    WrongTarget {
+        objective: Objective,
+        target: u8,
+        optimum: usize,
+    },
+    /// The level changes when it goes through a share code.
+    ShareCode(String),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Unsolvable(objective) => {
+                write!(f, "no placement found for {}", objective.target_name())
+            }
+            Problem::RulesMismatch {
+                objective,
+                solver_score,
+                game_score: Some(game_score),
+            } => write!(
+                f,
+                "the {} placement scores {solver_score} in the solver but {game_score} in the game",
+                objective.target_name()
+            ),
+            Problem::RulesMismatch {
+                objective,
+                solver_score,
+                game_score: None,
+            } => write!(
+                f,
+                "the {} placement scores {solver_score} in the solver but does not clear the level in the game",
+                objective.target_name()
+            ),
+            Problem::WrongTarget {
+                objective,
+                target,
+                optimum,
+            } => write!(
+                f,
+                "meta.{} is {target} but the optimum is {optimum}",
+                objective.target_name()
+            ),
+            Problem::ShareCode(reason) => write!(f, "the share code {reason}"),
+        }
+    }
+}
+
+/// Solves both missions of the level and replays the placements with the game's rules.
+pub fn validate_level(level: &LevelLayout) -> Vec<Problem> {
+    [Objective::MinimalBombs, Objective::MinimalAffectedCells]
+        .into_iter()
+        .filter_map(|objective| validate_mission(level, objective))
+        .chain(validate_share_code(level))
+        .collect()
+}
+
+/// Encodes the level with a bomb on every empty cell and checks that both decode unchanged.
+fn validate_share_code(level: &LevelLayout) -> Option<Problem> {
+    let placements: Vec<_> = level
+        .cells()
+        .filter(|coord| !level.objects.contains_key(coord))
+        .map(|coord| (coord, Item::BombSmall))
+        .collect();
+
+    let (decoded, decoded_placements) =
+        match decode_share_code(&encode_share_code(level, &placements)) {
+            Ok(decoded) => decoded,
+            Err(err) => return Some(Problem::ShareCode(format!("does not decode: {err}"))),
+        };
+
+    if decoded.to_ron().ok() != level.to_ron().ok() {
+        Some(Problem::ShareCode("changes the level".to_string()))
+    } else if decoded_placements != placements {
+        Some(Problem::ShareCode("changes the placements".to_string()))
+    } else {
+        None
+    }
+}
+
+fn validate_mission(level: &LevelLayout, objective: Objective) -> Option<Problem> {
+    let solution = match objective {
+        Objective::MinimalBombs => LpSolver.solve_minimal_bombs(level),
+        Objective::MinimalAffectedCells => LpSolver.solve_minimal_affected_areas(level),
+    };
+    let Ok(solution) = solution else {
+        return Some(Problem::Unsolvable(objective));
+    };
+
+    let mut simulation = solution.simulation();
+    simulation.run();
+    let outcome = simulation.outcome();
+
+    let (solver_score, game_score, target) = match objective {
+        Objective::MinimalBombs => (
+            solution.bombs.len(),
+            outcome.used_bomb_count as usize,
+            level.meta.min_bombs,
+        ),
+        Objective::MinimalAffectedCells => (
+            solution.count_affected_cells(),
+            outcome.affected_cell_count as usize,
+            level.meta.min_affected_cells,
+        ),
+    };
+
+    if !outcome.is_cleared || solver_score != game_score {
+        return Some(Problem::RulesMismatch {
+            objective,
+            solver_score,
+            game_score: outcome.is_cleared.then_some(game_score),
+        });
+    }
+
+    (target as usize != solver_score).then_some(Problem::WrongTarget {
+        objective,
+        target,
+        optimum: solver_score,
+    })
+}
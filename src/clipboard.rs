@@ -0,0 +1,53 @@
+//! Copying and pasting text through the system clipboard, which the web build can't reach.
+
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_non_send_resource(SystemClipboard::open());
+}
+
+/// The system clipboard, kept open for the whole run since on Linux copied text is only
+/// available while its owner is alive.
+pub struct SystemClipboard {
+    #[cfg(not(target_family = "wasm"))]
+    clipboard: Option<arboard::Clipboard>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl SystemClipboard {
+    fn open() -> Self {
+        let clipboard = arboard::Clipboard::new()
+            .inspect_err(|error| warn!("Failed to open the clipboard: {error}"))
+            .ok();
+        Self { clipboard }
+    }
+
+    pub fn set_text(&mut self, text: &str) -> anyhow::Result<()> {
+        Ok(self.get()?.set_text(text)?)
+    }
+
+    pub fn get_text(&mut self) -> anyhow::Result<String> {
+        Ok(self.get()?.get_text()?)
+    }
+
+    fn get(&mut self) -> anyhow::Result<&mut arboard::Clipboard> {
+        self.clipboard
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("the clipboard is unavailable"))
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl SystemClipboard {
+    fn open() -> Self {
+        Self {}
+    }
+
+    pub fn set_text(&mut self, _text: &str) -> anyhow::Result<()> {
+        anyhow::bail!("the web build can't reach the clipboard")
+    }
+
+    pub fn get_text(&mut self) -> anyhow::Result<String> {
+        anyhow::bail!("the web build can't reach the clipboard")
+    }
+}
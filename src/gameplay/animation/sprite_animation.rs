@@ -45,9 +45,15 @@ pub fn explosion(
 ) {
     if trigger.item.is_bomb() {
         explode_bomb(&mut commands, trigger.parent_entity, &asset, fire_query);
-    } else if trigger.item == Item::Rock || trigger.item == Item::Jewel {
+    } else if matches!(
+        trigger.item,
+        Item::Rock | Item::HardRock | Item::Jewel | Item::Enemy
+    ) {
         let mut entity_builder = commands.entity(trigger.parent_entity);
         explode_object(&mut entity_builder, trigger.item, &asset);
+    } else if matches!(trigger.item, Item::Mirror(_)) {
+        let mut entity_builder = commands.entity(trigger.parent_entity);
+        deflect_blast(&mut entity_builder, &asset);
     } else {
         warn!("Unexpected item type for explosion: {:?}", trigger.item);
         return;
@@ -99,8 +105,9 @@ fn explode_object(entity_builder: &mut EntityCommands, item: Item, asset: &Explo
         .and_modify(move |mut sprite| {
             sprite.texture_atlas.iter_mut().for_each(|atlas| {
                 atlas.index = match item {
-                    Item::Rock => 9,   // index for destroyed rock
-                    Item::Jewel => 11, // index for destroyed gem
+                    Item::Rock | Item::HardRock => 9, // index for destroyed rock
+                    Item::Jewel => 11,                // index for destroyed gem
+                    Item::Enemy => 22,                // index for destroyed enemy
                     _ => unreachable!(),
                 };
             });
@@ -123,6 +130,24 @@ fn explode_object(entity_builder: &mut EntityCommands, item: Item, asset: &Explo
     });
 }
 
+fn deflect_blast(entity_builder: &mut EntityCommands, asset: &ExplosionAssets) {
+    // the mirror survives, so only a small blast flashes over it
+    entity_builder.with_children(|parent| {
+        parent.spawn((
+            Name::new("Deflection Animation"),
+            Sprite::from_atlas_image(
+                Handle::clone(&asset.explosion_image),
+                TextureAtlas {
+                    layout: Handle::clone(&asset.texture_atlas_layout),
+                    index: 9, // dummy empty index
+                },
+            ),
+            Transform::from_xyz(0.0, 0.0, 4.0).with_scale(Vec2::splat(1.5).extend(1.0)),
+            ExplodeAnimation::new(),
+        ));
+    });
+}
+
 /// Update the animation timer.
 fn update_animation_timer<D>(time: Res<Time>, mut query: Query<&mut D>)
 where
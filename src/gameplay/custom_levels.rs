@@ -0,0 +1,145 @@
+//! Levels made by players, loaded from a folder next to the saved data instead of the assets.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use bombombo_core::encode_share_code;
+
+use crate::{
+    gameplay::{
+        CurrentLevel, GamePhase, GridCoord, Item, LevelLayout, edit::CurrentPlacement,
+        init_level::parse_level_layout, result::data_dir,
+    },
+    screens::Screen,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CustomLevels>()
+        .add_observer(play_shared_level);
+}
+
+/// The custom levels found by the last [`scan_custom_levels`], sorted by file name.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CustomLevels(pub Vec<CustomLevel>);
+
+#[derive(Debug, Clone)]
+pub struct CustomLevel {
+    pub name: String,
+    /// The hash of the level file, keying its progress.
+    pub hash: u64,
+    pub layout: Handle<LevelLayout>,
+}
+
+/// The folder players put their `.ron` and `.grid` level files in.
+pub fn custom_levels_dir() -> PathBuf {
+    data_dir().join("levels")
+}
+
+/// Reads the level files of the custom levels folder, creating the folder if it is missing so that
+/// players can find it. Files that don't parse are skipped, and the web build finds no folder.
+pub fn scan_custom_levels(
+    mut custom_levels: ResMut<CustomLevels>,
+    mut level_layouts: ResMut<Assets<LevelLayout>>,
+) {
+    // the layouts of the last scan are dropped with their handles
+    custom_levels.0.clear();
+
+    let dir = custom_levels_dir();
+    if let Err(error) = std::fs::create_dir_all(&dir) {
+        warn!(
+            "Failed to create the custom levels folder {}: {error}",
+            dir.display()
+        );
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        warn!("Failed to read the custom levels folder {}", dir.display());
+        return;
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "ron" || extension == "grid")
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let layout = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| {
+                let layout = parse_level_layout(&path, &bytes)?;
+                Ok((content_hash(&bytes), layout))
+            });
+        match layout {
+            Ok((hash, layout)) => custom_levels.0.push(CustomLevel {
+                name: layout.meta.name.clone(),
+                hash,
+                layout: level_layouts.add(layout),
+            }),
+            Err(error) => warn!("Skipping custom level {}: {error}", path.display()),
+        }
+    }
+}
+
+/// FNV-1a, which unlike the hasher of the standard library stays the same across releases.
+fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub fn move_to_custom_level(
+    custom_level: &CustomLevel,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    current_level.level = usize::MAX;
+    current_level.layout = Handle::clone(&custom_level.layout);
+    current_level.is_playtest = false;
+    current_level.custom = Some(custom_level.hash);
+    next_screen.set(Screen::Gameplay);
+    next_phase.set(GamePhase::Init);
+}
+
+/// Plays a level read from a share code, with the bombs the code carries already placed.
+#[derive(Event, Debug, Clone)]
+pub struct PlaySharedLevel {
+    pub level: LevelLayout,
+    pub placements: Vec<(GridCoord, Item)>,
+}
+
+/// Shared levels count as custom levels, keyed by the code of the level without placements.
+fn play_shared_level(
+    trigger: Trigger<PlaySharedLevel>,
+    mut level_layouts: ResMut<Assets<LevelLayout>>,
+    mut current_placement: ResMut<CurrentPlacement>,
+    current_level: ResMut<CurrentLevel>,
+    next_phase: ResMut<NextState<GamePhase>>,
+    next_screen: ResMut<NextState<Screen>>,
+) {
+    let PlaySharedLevel { level, placements } = trigger.event().clone();
+    // codes are typed by hand, so only bombs on empty cells of the board are trusted
+    let (width, height) = level.board_size;
+    let placements = placements
+        .into_iter()
+        .filter(|(coord, item)| {
+            item.is_bomb()
+                && coord.x < width
+                && coord.y < height
+                && !level.objects.contains_key(coord)
+        })
+        .collect();
+    let custom_level = CustomLevel {
+        name: level.meta.name.clone(),
+        hash: content_hash(encode_share_code(&level, &[]).as_bytes()),
+        layout: level_layouts.add(level),
+    };
+
+    *current_placement = CurrentPlacement::new(usize::MAX, placements);
+    move_to_custom_level(&custom_level, current_level, next_phase, next_screen);
+}
@@ -1,14 +1,20 @@
 use bevy::prelude::*;
+use bombombo_core::Facing;
 
 use crate::{
     PausableSystems,
     audio::{SEVolume, SoundEffectAssets, sound_effect},
     gameplay::{
-        CurrentLevel, FireAnimation, GamePhase, GridCoord, Item, ItemAssets, ItemState,
+        CurrentLevel, FireAnimation, GamePhase, GridCoord, Item, ItemAssets, LevelLayout,
         init_level::{GridTile, LevelBase},
+        item_rotation,
     },
     screens::Screen,
-    theme::{UiAssets, widget},
+    theme::{
+        UiAssets,
+        palette::{BUTTON_TEXT, BUTTON_TEXT_DISABLED},
+        widget,
+    },
 };
 
 pub(super) fn plugin(app: &mut App) {
@@ -17,7 +23,10 @@ pub(super) fn plugin(app: &mut App) {
     // app.register_type::<ItemAssets>();
 
     app.init_resource::<SelectedItem>()
-        .init_resource::<CurrentPlacement>();
+        .init_resource::<CurrentPlacement>()
+        .init_resource::<PlacementHistory>()
+        .init_resource::<HoveredTile>()
+        .init_resource::<RotationDrag>();
 
     app.add_observer(create_object);
     // .add_observer(try_create_single_fire);
@@ -30,17 +39,29 @@ pub(super) fn plugin(app: &mut App) {
             init_edit_state,
         ),
     )
-    .add_systems(OnExit(Screen::Gameplay), reset_current_placement)
-    .add_systems(OnEnter(GamePhase::Edit), apply_current_placement)
+    .add_systems(
+        OnExit(Screen::Gameplay),
+        (reset_current_placement, reset_placement_history),
+    )
+    .add_systems(
+        OnEnter(GamePhase::Edit),
+        (apply_current_placement, sync_placement_history),
+    )
     .add_systems(
         Update,
-        (reset_all_object_placements, run_simulation_with_keyboard)
+        (
+            reset_all_object_placements,
+            run_simulation_with_keyboard,
+            step_placement_history_with_keyboard,
+            rotate_with_keyboard,
+            update_item_counts,
+        )
             .run_if(in_state(GamePhase::Edit))
             .in_set(PausableSystems),
     )
     .add_systems(
         Update,
-        highlight_selected_item
+        (highlight_selected_item, turn_item_buttons)
             .run_if(in_state(GamePhase::Edit).and(resource_changed::<SelectedItem>)),
     );
 }
@@ -49,57 +70,27 @@ fn spawn_item_buttons(
     mut commands: Commands,
     item_assets: Res<ItemAssets>,
     ui_assets: Res<UiAssets>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelLayout>>,
 ) {
+    let Some(level_layout) = level_assets.get(&current_level.layout) else {
+        warn!("Current level layout not found in assets");
+        return;
+    };
+
+    let items: Vec<_> = level_layout
+        .inventory()
+        .iter()
+        .map(|slot| slot.item)
+        .chain([Item::Eraser])
+        .collect();
+
     commands
         .spawn((
             widget::ui_root("Item Buttons"),
             GlobalZIndex(0),
             LevelBase,
             StateScoped(Screen::Gameplay),
-            children![
-                widget::item_button(
-                    Handle::clone(&item_assets.sprite_sheet),
-                    &ui_assets,
-                    Handle::clone(&item_assets.texture_atlas_layout),
-                    Item::BombSmall,
-                    select_item::<0>
-                ),
-                widget::item_button(
-                    Handle::clone(&item_assets.sprite_sheet),
-                    &ui_assets,
-                    Handle::clone(&item_assets.texture_atlas_layout),
-                    Item::BombMedium,
-                    select_item::<1>
-                ),
-                // widget::item_button(
-                //     Handle::clone(&item_assets.sprite_sheet),
-                //     &ui_assets,
-                //     Handle::clone(&item_assets.texture_atlas_layout),
-                //     Item::BombLarge,
-                //     select_item::<2>
-                // ),
-                widget::item_button(
-                    Handle::clone(&item_assets.sprite_sheet),
-                    &ui_assets,
-                    Handle::clone(&item_assets.texture_atlas_layout),
-                    Item::BombHorizontal,
-                    select_item::<3>
-                ),
-                widget::item_button(
-                    Handle::clone(&item_assets.sprite_sheet),
-                    &ui_assets,
-                    Handle::clone(&item_assets.texture_atlas_layout),
-                    Item::BombVertical,
-                    select_item::<4>
-                ),
-                widget::item_button(
-                    Handle::clone(&item_assets.sprite_sheet),
-                    &ui_assets,
-                    Handle::clone(&item_assets.texture_atlas_layout),
-                    Item::Eraser,
-                    select_item::<255> // Eraser
-                ),
-            ],
         ))
         .insert(Node {
             position_type: PositionType::Absolute,
@@ -110,6 +101,17 @@ fn spawn_item_buttons(
             row_gap: Val::Px(16.0),
             left: Val::Percent(80.0),
             ..Default::default()
+        })
+        .with_children(|parent| {
+            for item in items {
+                parent.spawn(widget::item_button(
+                    Handle::clone(&item_assets.sprite_sheet),
+                    &ui_assets,
+                    Handle::clone(&item_assets.texture_atlas_layout),
+                    item,
+                    select_item,
+                ));
+            }
         });
 }
 
@@ -134,6 +136,18 @@ fn spawn_controlflow_buttons(mut commands: Commands, ui_assets: Res<UiAssets>) {
         })
         .with_children(|parent| {
             // parent.spawn(widget::menu_button(&ui_assets));
+            parent.spawn((
+                Name::new("History Buttons"),
+                Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(16.0),
+                    ..Default::default()
+                },
+                children![
+                    widget::button_medium("Undo", &ui_assets, undo_with_button),
+                    widget::button_medium("Redo", &ui_assets, redo_with_button),
+                ],
+            ));
             parent.spawn(widget::run_button(&ui_assets, run_simulation_with_button));
         });
 }
@@ -148,6 +162,9 @@ pub struct CreateObject {
     pub coord: GridCoord,
     pub item: Item,
     with_sound: bool,
+    with_history: bool,
+    /// The undo or redo this object replays, moved to the other stack once it is applied.
+    history_step: Option<HistoryStep>,
 }
 
 impl CreateObject {
@@ -157,9 +174,22 @@ impl CreateObject {
             coord,
             item,
             with_sound: true,
+            with_history: false,
+            history_step: None,
         }
     }
 
+    /// Records the change in the [`PlacementHistory`] so that it can be undone.
+    pub fn with_history(mut self) -> Self {
+        self.with_history = true;
+        self
+    }
+
+    fn replaying(mut self, step: HistoryStep) -> Self {
+        self.history_step = Some(step);
+        self
+    }
+
     pub fn without_sound(mut self) -> Self {
         self.with_sound = false;
         self
@@ -185,13 +215,19 @@ pub struct Fire;
 #[derive(Resource, Debug, Clone, Copy, Default)]
 pub(super) struct SelectedItem(pub Option<Item>);
 
-fn select_item<const I: u8>(
-    _: Trigger<Pointer<Click>>,
+fn select_item(
+    trigger: Trigger<Pointer<Click>>,
+    item_query: Query<&Item, With<widget::ItemButton>>,
     mut selected_item: ResMut<SelectedItem>,
     game_phase: Res<State<GamePhase>>,
 ) {
-    let item = Item::from(I);
-    selected_item.0 = if selected_item.0 == Some(item) || *game_phase.get() != GamePhase::Edit {
+    let Ok(&item) = item_query.get(trigger.target()) else {
+        return;
+    };
+    let is_selected = selected_item
+        .0
+        .is_some_and(|selected| selected.same_kind(&item));
+    selected_item.0 = if is_selected || *game_phase.get() != GamePhase::Edit {
         None
     } else {
         Some(item)
@@ -207,7 +243,10 @@ fn highlight_selected_item(
             .texture_atlas
             .iter_mut()
             .for_each(|texture_atlas| {
-                texture_atlas.index = if selected_item.0 == Some(item) {
+                texture_atlas.index = if selected_item
+                    .0
+                    .is_some_and(|selected| selected.same_kind(&item))
+                {
                     0 // Highlighted state
                 } else {
                     1 // Normal state
@@ -216,20 +255,287 @@ fn highlight_selected_item(
     }
 }
 
+/// Turns the button of the selected directional bomb the way the selection faces, so that
+/// picking it again keeps the facing.
+fn turn_item_buttons(
+    selected_item: Res<SelectedItem>,
+    mut button_query: Query<(&mut Item, &Children), With<widget::ItemButton>>,
+    mut image_query: Query<&mut Transform, With<ImageNode>>,
+) {
+    let Some(selected) = selected_item
+        .0
+        .filter(|selected| selected.facing().is_some())
+    else {
+        return;
+    };
+
+    for (mut item, children) in &mut button_query {
+        if !item.same_kind(&selected) {
+            continue;
+        }
+        *item = selected;
+        for &child in children {
+            if let Ok(mut transform) = image_query.get_mut(child) {
+                transform.rotation = item_rotation(selected);
+            }
+        }
+    }
+}
+
+/// The enabled tile under the cursor, whose directional bomb the rotation keys turn.
+#[derive(Resource, Debug, Default)]
+pub(super) struct HoveredTile(Option<Entity>);
+
+pub(super) fn track_hovered_tile(
+    over: Trigger<Pointer<Over>>,
+    mut hovered_tile: ResMut<HoveredTile>,
+) {
+    hovered_tile.0 = Some(over.target());
+}
+
+pub(super) fn forget_hovered_tile(
+    _out: Trigger<Pointer<Out>>,
+    mut hovered_tile: ResMut<HoveredTile>,
+) {
+    hovered_tile.0 = None;
+}
+
+/// Turns the directional bomb under the cursor a quarter with Q and E, or the selected one
+/// when there is none.
+fn rotate_with_keyboard(
+    button_input: Res<ButtonInput<KeyCode>>,
+    hovered_tile: Res<HoveredTile>,
+    tile_query: Query<&GridCoord, With<GridTile>>,
+    item_query: Query<(&Item, &GridCoord)>,
+    mut selected_item: ResMut<SelectedItem>,
+    mut commands: Commands,
+) {
+    let rotate = if button_input.just_pressed(KeyCode::KeyE) {
+        Item::rotated_clockwise
+    } else if button_input.just_pressed(KeyCode::KeyQ) {
+        Item::rotated_counter_clockwise
+    } else {
+        return;
+    };
+
+    let hovered_bomb = hovered_tile.0.and_then(|tile| {
+        let &coord = tile_query.get(tile).ok()?;
+        directional_bomb_at(item_query, coord).map(|&item| (tile, coord, item))
+    });
+    if let Some((tile, coord, item)) = hovered_bomb {
+        commands.trigger(
+            CreateObject::new(tile, coord, rotate(item))
+                .without_sound()
+                .with_history(),
+        );
+        return;
+    }
+
+    if let Some(selected) = selected_item
+        .0
+        .filter(|selected| selected.facing().is_some())
+    {
+        selected_item.0 = Some(rotate(selected));
+    }
+}
+
+/// Pointer distance a secondary button drag has to cover before it turns a bomb.
+const ROTATION_DRAG_THRESHOLD: f32 = 16.0;
+
+/// Whether the secondary button drag in progress has turned a directional bomb, in which
+/// case releasing the button doesn't erase it.
+#[derive(Resource, Debug, Default)]
+pub(super) struct RotationDrag {
+    has_rotated: bool,
+}
+
+impl RotationDrag {
+    pub(super) fn start(&mut self) {
+        self.has_rotated = false;
+    }
+}
+
+/// Turns the directional bomb of the dragged tile towards the secondary button drag.
+pub(super) fn rotate_by_dragging(
+    drag: Trigger<Pointer<Drag>>,
+    tile_query: Query<&GridCoord, With<GridTile>>,
+    item_query: Query<(&Item, &GridCoord)>,
+    mut rotation_drag: ResMut<RotationDrag>,
+    mut commands: Commands,
+) {
+    if drag.button != PointerButton::Secondary || drag.distance.length() < ROTATION_DRAG_THRESHOLD {
+        return;
+    }
+    let tile = drag.target();
+    let Ok(&coord) = tile_query.get(tile) else {
+        return;
+    };
+    let Some(&item) = directional_bomb_at(item_query, coord) else {
+        return;
+    };
+
+    // pointer coordinates grow downwards
+    let facing = if drag.distance.x.abs() > drag.distance.y.abs() {
+        if drag.distance.x > 0.0 {
+            Facing::Right
+        } else {
+            Facing::Left
+        }
+    } else if drag.distance.y > 0.0 {
+        Facing::Down
+    } else {
+        Facing::Up
+    };
+
+    rotation_drag.has_rotated = true;
+    let rotated = item.facing_towards(facing);
+    if rotated != item {
+        commands.trigger(
+            CreateObject::new(tile, coord, rotated)
+                .without_sound()
+                .with_history(),
+        );
+    }
+}
+
+/// Erases the directional bomb of the tile on a secondary click that didn't turn it.
+pub(super) fn erase_directional_bomb_on_click(
+    click: Trigger<Pointer<Click>>,
+    tile_query: Query<&GridCoord, With<GridTile>>,
+    item_query: Query<(&Item, &GridCoord)>,
+    rotation_drag: Res<RotationDrag>,
+    mut commands: Commands,
+) {
+    if click.button != PointerButton::Secondary || rotation_drag.has_rotated {
+        return;
+    }
+    let tile = click.target();
+    let Ok(&coord) = tile_query.get(tile) else {
+        return;
+    };
+    if directional_bomb_at(item_query, coord).is_some() {
+        commands.trigger(
+            CreateObject::new(tile, coord, Item::Eraser)
+                .with_sound()
+                .with_history(),
+        );
+    }
+}
+
+pub(super) fn directional_bomb_at<'a>(
+    item_query: Query<'a, '_, (&Item, &GridCoord)>,
+    coord: GridCoord,
+) -> Option<&'a Item> {
+    item_query
+        .into_iter()
+        .find(|&(item, &item_coord)| item_coord == coord && item.facing().is_some())
+        .map(|(item, _)| item)
+}
+
+/// How many more bombs of `item` the player can place besides `placed`, `None` when unlimited.
+///
+/// Objects of the level itself are skipped, so `placed` can list every item on the board.
+fn remaining_count<'a>(
+    level_layout: &LevelLayout,
+    item: Item,
+    placed: impl IntoIterator<Item = (&'a Item, &'a GridCoord)>,
+) -> Option<u8> {
+    let limit = level_layout.item_limit(item)?;
+    let used = placed
+        .into_iter()
+        .filter(|&(placed_item, coord)| {
+            placed_item.same_kind(&item) && !level_layout.objects.contains_key(coord)
+        })
+        .count();
+    Some(limit.saturating_sub(used.try_into().unwrap_or(u8::MAX)))
+}
+
+fn update_item_counts(
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelLayout>>,
+    placed_query: Query<(&Item, &GridCoord)>,
+    button_query: Query<(&Item, &Children), With<widget::ItemButton>>,
+    mut image_query: Query<&mut ImageNode, Without<widget::ItemButton>>,
+    mut count_query: Query<(&mut Text, &mut TextColor), With<widget::ItemCount>>,
+) {
+    let Some(level_layout) = level_assets.get(&current_level.layout) else {
+        return;
+    };
+
+    for (&item, children) in &button_query {
+        let remaining = if item.is_bomb() {
+            remaining_count(level_layout, item, placed_query)
+        } else {
+            None
+        };
+        let is_used_up = remaining == Some(0);
+
+        for &child in children {
+            if let Ok(mut image_node) = image_query.get_mut(child) {
+                // gray out the item image when there is nothing left to place
+                image_node.color = if is_used_up {
+                    Color::srgba(0.5, 0.5, 0.5, 0.5)
+                } else {
+                    Color::WHITE
+                };
+            }
+            if let Ok((mut text, mut text_color)) = count_query.get_mut(child) {
+                text.0 = remaining.map(|count| count.to_string()).unwrap_or_default();
+                text_color.0 = if is_used_up {
+                    BUTTON_TEXT_DISABLED
+                } else {
+                    BUTTON_TEXT
+                };
+            }
+        }
+    }
+}
+
 // create item on grid click
 fn create_object(
     trigger: Trigger<CreateObject>,
     mut commands: Commands,
     item_assets: Res<ItemAssets>,
     query: Query<(Entity, &Item, &GridCoord)>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelLayout>>,
     se_assets: Option<Res<SoundEffectAssets>>,
     se_volume: Res<SEVolume>,
+    mut placement_history: ResMut<PlacementHistory>,
 ) {
     let event = trigger.event();
 
-    if let Some((existing_entity, _item, _coord)) =
-        query.iter().find(|&(_, _, coord)| coord == &event.coord)
-    {
+    let existing = query.iter().find(|&(_, _, coord)| coord == &event.coord);
+
+    // refuse bombs beyond the inventory, not counting the one being replaced
+    if event.item.is_bomb() {
+        let others = query
+            .iter()
+            .filter(|&(_, _, coord)| coord != &event.coord)
+            .map(|(_, item, coord)| (item, coord));
+        let remaining = level_assets
+            .get(&current_level.layout)
+            .and_then(|level_layout| remaining_count(level_layout, event.item, others));
+        if remaining == Some(0) {
+            return;
+        }
+    }
+
+    if let Some(step) = event.history_step {
+        placement_history.step(step);
+    }
+    if event.with_history {
+        let change = PlacementChange {
+            coord: event.coord,
+            before: existing.map(|(_, &item, _)| item),
+            after: (event.item != Item::Eraser).then_some(event.item),
+        };
+        if change.before != change.after {
+            placement_history.record(change);
+        }
+    }
+
+    if let Some((existing_entity, _item, _coord)) = existing {
         commands.entity(existing_entity).despawn();
     }
 
@@ -247,15 +553,18 @@ fn create_object(
             Name::new("Item Object"),
             GridCoord::clone(&event.coord),
             Item::clone(&event.item),
-            ItemState::None,
             Sprite::from_atlas_image(
                 item_assets.sprite_sheet.clone(),
                 TextureAtlas {
                     layout: item_assets.texture_atlas_layout.clone(),
-                    index: event.item as usize,
+                    index: event.item.to_sprite_index(),
                 },
             ),
-            Transform::from_scale(Vec3::splat(2.0)).with_translation(Vec3::new(0.0, 0.0, 1.0)),
+            Transform::from_scale(Vec3::splat(2.0))
+                .with_translation(Vec3::new(0.0, 0.0, 1.0))
+                .with_rotation(item_rotation(event.item)),
+            // pointer events go to the tile, which outlives the items replaced on it
+            Pickable::IGNORE,
             StateScoped(Screen::Gameplay),
         ))
         .id();
@@ -316,8 +625,14 @@ pub fn fire(coord: GridCoord, item_assets: &ItemAssets) -> impl Bundle {
 fn reset_all_object_placements(
     button_input: Res<ButtonInput<KeyCode>>,
     mut next_state: ResMut<NextState<GamePhase>>,
+    mut current_placement: ResMut<CurrentPlacement>,
+    mut placement_history: ResMut<PlacementHistory>,
 ) {
     if button_input.just_pressed(KeyCode::KeyR) {
+        // the placements of the last run are not brought back, and the history no longer
+        // matches the emptied board
+        current_placement.placements.clear();
+        placement_history.clear();
         next_state.set(GamePhase::Init);
     }
 }
@@ -372,6 +687,16 @@ impl CurrentPlacement {
     pub fn new(level: usize, placements: Vec<(GridCoord, Item)>) -> Self {
         Self { level, placements }
     }
+
+    #[cfg(feature = "dev_native")]
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    #[cfg(feature = "dev_native")]
+    pub fn placements(&self) -> &[(GridCoord, Item)] {
+        &self.placements
+    }
 }
 
 fn apply_current_placement(
@@ -401,3 +726,153 @@ fn reset_current_placement(mut current_placement: ResMut<CurrentPlacement>) {
     current_placement.placements.clear(); // Clear the current placement
     current_placement.level = usize::MAX; // Reset the level to an invalid state
 }
+
+/// A change the player made to one cell in the edit phase.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq)]
+struct PlacementChange {
+    coord: GridCoord,
+    before: Option<Item>,
+    after: Option<Item>,
+}
+
+/// Undo and redo stacks of the player's placements. Like [`CurrentPlacement`], they are kept
+/// across retries of the same level.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct PlacementHistory {
+    level: usize,
+    undo_stack: Vec<PlacementChange>,
+    redo_stack: Vec<PlacementChange>,
+}
+
+impl PlacementHistory {
+    fn record(&mut self, change: PlacementChange) {
+        self.undo_stack.push(change);
+        self.redo_stack.clear();
+    }
+
+    fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    /// The change the next `step` replays.
+    fn peek(&self, step: HistoryStep) -> Option<PlacementChange> {
+        match step {
+            HistoryStep::Undo => self.undo_stack.last().copied(),
+            HistoryStep::Redo => self.redo_stack.last().copied(),
+        }
+    }
+
+    /// Moves the change replayed by `step` to the other stack.
+    fn step(&mut self, step: HistoryStep) {
+        let (from, to) = match step {
+            HistoryStep::Undo => (&mut self.undo_stack, &mut self.redo_stack),
+            HistoryStep::Redo => (&mut self.redo_stack, &mut self.undo_stack),
+        };
+        if let Some(change) = from.pop() {
+            to.push(change);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryStep {
+    Undo,
+    Redo,
+}
+
+fn step_placement_history_with_keyboard(
+    button_input: Res<ButtonInput<KeyCode>>,
+    commands: Commands,
+    placement_history: ResMut<PlacementHistory>,
+    grid_query: Query<(&GridCoord, Entity), With<GridTile>>,
+) {
+    if !button_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    let is_shift_pressed = button_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let step = if button_input.just_pressed(KeyCode::KeyY)
+        || (button_input.just_pressed(KeyCode::KeyZ) && is_shift_pressed)
+    {
+        HistoryStep::Redo
+    } else if button_input.just_pressed(KeyCode::KeyZ) {
+        HistoryStep::Undo
+    } else {
+        return;
+    };
+
+    step_placement_history(step, commands, placement_history, grid_query);
+}
+
+fn undo_with_button(
+    _trigger: Trigger<Pointer<Click>>,
+    state: Res<State<GamePhase>>,
+    commands: Commands,
+    placement_history: ResMut<PlacementHistory>,
+    grid_query: Query<(&GridCoord, Entity), With<GridTile>>,
+) {
+    if *state.get() != GamePhase::Edit {
+        return;
+    }
+
+    step_placement_history(HistoryStep::Undo, commands, placement_history, grid_query);
+}
+
+fn redo_with_button(
+    _trigger: Trigger<Pointer<Click>>,
+    state: Res<State<GamePhase>>,
+    commands: Commands,
+    placement_history: ResMut<PlacementHistory>,
+    grid_query: Query<(&GridCoord, Entity), With<GridTile>>,
+) {
+    if *state.get() != GamePhase::Edit {
+        return;
+    }
+
+    step_placement_history(HistoryStep::Redo, commands, placement_history, grid_query);
+}
+
+fn step_placement_history(
+    step: HistoryStep,
+    mut commands: Commands,
+    placement_history: ResMut<PlacementHistory>,
+    grid_query: Query<(&GridCoord, Entity), With<GridTile>>,
+) {
+    // the change stays on its stack until it is applied, the inventory may still refuse it
+    let Some(change) = placement_history.peek(step) else {
+        return;
+    };
+
+    let item = match step {
+        HistoryStep::Undo => change.before,
+        HistoryStep::Redo => change.after,
+    };
+    let Some((_, parent_grid)) = grid_query
+        .iter()
+        .find(|&(&grid_coord, _)| grid_coord == change.coord)
+    else {
+        warn!("No grid tile found for coord: {:?}", change.coord);
+        return;
+    };
+    commands.trigger(
+        CreateObject::new(parent_grid, change.coord, item.unwrap_or(Item::Eraser)).replaying(step),
+    );
+}
+
+/// Starts a new history when a different level is entered.
+fn sync_placement_history(
+    current_level: Res<CurrentLevel>,
+    mut placement_history: ResMut<PlacementHistory>,
+) {
+    if placement_history.level != current_level.level {
+        placement_history.clear();
+        placement_history.level = current_level.level;
+    }
+}
+
+fn reset_placement_history(mut placement_history: ResMut<PlacementHistory>) {
+    placement_history.clear();
+    placement_history.level = usize::MAX; // Reset the level to an invalid state
+}
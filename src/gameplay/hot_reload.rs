@@ -0,0 +1,192 @@
+//! Rebuild the level when its file changes on disk, and show the level files that fail to load.
+
+use bevy::{
+    asset::{AssetLoadFailedEvent, AssetPath},
+    ecs::spawn::SpawnIter,
+    prelude::*,
+};
+
+use crate::{
+    gameplay::{
+        CurrentLevel, GamePhase, GridCoord, Item, LevelLayout, edit::CurrentPlacement,
+        init_level::GridTile,
+    },
+    screens::Screen,
+    theme::{
+        UiAssets,
+        palette::{SUB_ACCENT_COLOR, TEXT},
+    },
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelLoadErrors>();
+    app.add_systems(
+        Update,
+        (
+            reload_current_level.run_if(in_state(Screen::Gameplay)),
+            (
+                record_level_load_errors,
+                forget_fixed_level_load_errors,
+                show_level_load_errors.run_if(resource_changed::<LevelLoadErrors>),
+            )
+                .chain(),
+        ),
+    );
+}
+
+/// The level files that failed to load with their errors, until they load again or the
+/// overlay listing them is clicked.
+#[derive(Resource, Debug, Default)]
+struct LevelLoadErrors(Vec<(AssetPath<'static>, String)>);
+
+/// Lists the [`LevelLoadErrors`].
+#[derive(Component, Debug)]
+struct LevelErrorOverlay;
+
+/// Restarts the edit phase on the modified level, bringing the player's placements along.
+fn reload_current_level(
+    mut asset_events: EventReader<AssetEvent<LevelLayout>>,
+    current_level: Res<CurrentLevel>,
+    level_layouts: Res<Assets<LevelLayout>>,
+    game_phase: Res<State<GamePhase>>,
+    item_query: Query<(&Item, &GridCoord, &ChildOf)>,
+    tile_query: Query<&GridTile>,
+    mut current_placement: ResMut<CurrentPlacement>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    let is_modified = asset_events
+        .read()
+        .any(|event| event.is_modified(&current_level.layout));
+    if !is_modified {
+        return;
+    }
+    let Some(level_layout) = level_layouts.get(&current_level.layout) else {
+        return;
+    };
+    info!("Reloading level {:?}", level_layout.meta.name);
+
+    // past the edit phase, the placements were recorded when the run started
+    let placements = if *game_phase.get() == GamePhase::Edit {
+        item_query
+            .iter()
+            .filter(|(_, _, ChildOf(tile))| {
+                tile_query
+                    .get(*tile)
+                    .is_ok_and(|tile| !tile.holds_level_object())
+            })
+            .map(|(&item, &coord, _)| (coord, item))
+            .collect()
+    } else if current_placement.level() == current_level.level {
+        current_placement.placements().to_vec()
+    } else {
+        Vec::new()
+    };
+
+    // keep the placements on cells the level still leaves empty, the inventory limits are checked
+    // again as they are placed
+    let (width, height) = level_layout.board_size;
+    let placements = placements
+        .into_iter()
+        .filter(|(coord, _)| {
+            coord.x < width && coord.y < height && !level_layout.objects.contains_key(coord)
+        })
+        .collect();
+
+    *current_placement = CurrentPlacement::new(current_level.level, placements);
+    next_phase.set(GamePhase::Init);
+}
+
+/// Remembers the level files that failed to load, replacing the error of a file that fails
+/// again.
+fn record_level_load_errors(
+    mut failed_events: EventReader<AssetLoadFailedEvent<LevelLayout>>,
+    mut level_load_errors: ResMut<LevelLoadErrors>,
+) {
+    for event in failed_events.read() {
+        error!("Failed to load level {}: {}", event.path, event.error);
+        level_load_errors.0.retain(|(path, _)| *path != event.path);
+        level_load_errors
+            .0
+            .push((event.path.clone(), event.error.to_string()));
+    }
+}
+
+/// Forgets the errors of the level files that loaded since.
+fn forget_fixed_level_load_errors(
+    mut asset_events: EventReader<AssetEvent<LevelLayout>>,
+    asset_server: Res<AssetServer>,
+    mut level_load_errors: ResMut<LevelLoadErrors>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::Added { id }
+        | AssetEvent::Modified { id }
+        | AssetEvent::LoadedWithDependencies { id }) = event
+        else {
+            continue;
+        };
+        let Some(loaded_path) = asset_server.get_path(*id) else {
+            continue;
+        };
+        // only touched when something is forgotten, the overlay is rebuilt on every change
+        if level_load_errors
+            .0
+            .iter()
+            .any(|(path, _)| *path == loaded_path)
+        {
+            level_load_errors.0.retain(|(path, _)| *path != loaded_path);
+        }
+    }
+}
+
+fn show_level_load_errors(
+    mut commands: Commands,
+    level_load_errors: Res<LevelLoadErrors>,
+    ui_assets: Option<Res<UiAssets>>,
+    overlay_query: Query<Entity, With<LevelErrorOverlay>>,
+) {
+    for entity in &overlay_query {
+        commands.entity(entity).despawn();
+    }
+    if level_load_errors.0.is_empty() {
+        return;
+    }
+
+    let errors: Vec<_> = level_load_errors
+        .0
+        .iter()
+        .map(|(path, error)| format!("{path}: {error}"))
+        .collect();
+    // levels fail to load before the UI assets are ready too
+    let font = ui_assets.map_or_else(default, |ui_assets| Handle::clone(&ui_assets.font));
+    commands
+        .spawn((
+            Name::new("Level Error Overlay"),
+            LevelErrorOverlay,
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                left: Val::Px(24.0),
+                right: Val::Px(24.0),
+                bottom: Val::Px(24.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(SUB_ACCENT_COLOR.with_alpha(0.9)),
+            GlobalZIndex(3),
+            Children::spawn(SpawnIter(errors.into_iter().map(move |error| {
+                (
+                    Name::new("Level Error"),
+                    Text(error),
+                    TextFont::from_font(Handle::clone(&font)).with_font_size(20.0),
+                    TextColor(TEXT),
+                    Pickable::IGNORE,
+                )
+            }))),
+        ))
+        .observe(
+            |_: Trigger<Pointer<Click>>, mut level_load_errors: ResMut<LevelLoadErrors>| {
+                level_load_errors.0.clear();
+            },
+        );
+}
@@ -1,6 +1,10 @@
 //! Spawn the main level.
 
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    fmt::{self, Debug},
+    ops::Range,
+    path::Path,
+};
 
 use bevy::{
     asset::{AssetLoader, LoadContext, io::Reader},
@@ -10,13 +14,19 @@ use bevy::{
     prelude::*,
 };
 use bevy_persistent::Persistent;
-use serde::{Deserialize, Serialize};
+use bombombo_core::{Board, LevelManifest, UnlockRule};
 
 use crate::{
     asset_tracking::LoadResource,
     gameplay::{
-        ClearedLevels, GamePhase, GameResult, GridCoord, Item,
-        edit::{CreateObject, SelectedItem, fire},
+        ClearedCustomLevels, ClearedLevels, GamePhase, GameResult, GridCoord, Item, LevelLayout,
+        edit::{
+            CreateObject, RotationDrag, SelectedItem, directional_bomb_at,
+            erase_directional_bomb_on_click, fire, forget_hovered_tile, rotate_by_dragging,
+            track_hovered_tile,
+        },
+        item_rotation,
+        result::best_result,
     },
     screens::Screen,
     theme::{
@@ -28,10 +38,11 @@ use crate::{
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<LevelAssets>()
         .init_asset::<LevelLayout>()
-        .init_asset_loader::<LevelLayoutLoader>();
+        .init_asset_loader::<LevelLayoutLoader>()
+        .init_asset_loader::<LevelManifestLoader>();
     app.load_resource::<BgAssets>()
         .load_resource::<ItemAssets>()
-        .load_resource::<LevelAssets>()
+        .load_resource_from_path::<LevelAssets>("levels/manifest.ron")
         .init_resource::<CurrentLevel>();
     app.add_systems(
         OnEnter(GamePhase::Init),
@@ -124,21 +135,18 @@ impl FromWorld for BgAssets {
 pub struct CurrentLevel {
     pub level: usize,
     pub layout: Handle<LevelLayout>,
+    /// A level opened from the level editor. It runs as soon as it is spawned, and its results
+    /// are not saved.
+    pub is_playtest: bool,
+    /// The hash of a level loaded from the custom levels folder, whose progress is kept in
+    /// [`ClearedCustomLevels`](crate::gameplay::ClearedCustomLevels). Custom levels have no
+    /// index and leave `level` at `usize::MAX`.
+    pub custom: Option<u64>,
 }
 
-#[derive(Asset, Debug, Clone, Reflect, Serialize, Deserialize)]
-pub struct LevelLayout {
-    pub board_size: (u8, u8),
-    pub objects: HashMap<GridCoord, Item>,
-    pub fire_coord: GridCoord,
-    pub meta: LevelMetaData,
-}
-
-#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
-pub struct LevelMetaData {
-    pub name: String,
-    pub min_bombs: u8,
-    pub min_affected_cells: u8,
+/// A run condition telling whether the current level is played from the level editor.
+pub fn is_playtest(current_level: Res<CurrentLevel>) -> bool {
+    current_level.is_playtest
 }
 
 #[derive(Default)]
@@ -152,16 +160,27 @@ impl AssetLoader for LevelLayoutLoader {
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let custom_asset = ron::de::from_bytes::<LevelLayout>(&bytes)?;
-        Ok(custom_asset)
+        parse_level_layout(load_context.path(), &bytes)
     }
 
     fn extensions(&self) -> &[&str] {
-        &["custom"]
+        &["ron", "grid"]
+    }
+}
+
+/// Reads a level file, written as grid text if its extension is `grid` and as RON otherwise.
+pub fn parse_level_layout(path: &Path, bytes: &[u8]) -> anyhow::Result<LevelLayout> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "grid")
+    {
+        Ok(LevelLayout::from_grid_text(str::from_utf8(bytes)?)?)
+    } else {
+        Ok(ron::de::from_bytes(bytes)?)
     }
 }
 
@@ -173,38 +192,147 @@ pub struct GridTile {
     enable_interactions: bool,
 }
 
+impl GridTile {
+    /// Whether the tile holds one of the level's own objects, which the player can't replace.
+    #[cfg(feature = "dev_native")]
+    pub fn holds_level_object(&self) -> bool {
+        !self.enable_interactions
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy, Default)]
 pub struct GridTileTint;
 
 #[derive(Resource, Asset, Debug, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct LevelAssets {
+    /// Every level in play order. Progress in [`ClearedLevels`] is keyed by index here.
+    #[dependency]
     pub levels: Vec<Handle<LevelLayout>>,
+    pub packs: Vec<LevelPackInfo>,
 }
 
-impl FromWorld for LevelAssets {
-    fn from_world(world: &mut World) -> Self {
-        let assets = world.resource::<AssetServer>();
-        Self {
-            levels: vec![
-                assets.load("levels/level_01.ron"),
-                assets.load("levels/level_02.ron"),
-                assets.load("levels/level_03.ron"),
-                assets.load("levels/level_04.ron"),
-                assets.load("levels/level_05.ron"),
-                assets.load("levels/level_06.ron"),
-                assets.load("levels/level_07.ron"),
-                assets.load("levels/level_11.ron"),
-                assets.load("levels/level_08.ron"),
-                assets.load("levels/level_20.ron"),
-                assets.load("levels/level_21.ron"),
-                assets.load("levels/level_12.ron"),
-                assets.load("levels/level_22.ron"),
-                assets.load("levels/level_23.ron"),
-                assets.load("levels/level_24.ron"),
-                assets.load("levels/level_25.ron"),
-            ],
+#[derive(Debug, Clone, Reflect)]
+pub struct LevelPackInfo {
+    pub name: String,
+    pub unlock: UnlockRule,
+    pub sequential: bool,
+    /// Indices into [`LevelAssets::levels`].
+    pub levels: Range<usize>,
+}
+
+/// What has to be done before a level can be played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockReason {
+    /// The level with this index has to be cleared first.
+    PreviousLevel(usize),
+    /// This many stars have to be earned.
+    Stars(u32),
+    /// Every level of the named pack has to be cleared.
+    Pack(String),
+}
+
+impl fmt::Display for LockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockReason::PreviousLevel(index) => write!(f, "Clear {}", index + 1),
+            LockReason::Stars(stars) => write!(f, "{stars} Stars"),
+            LockReason::Pack(name) => write!(f, "Clear {name}"),
+        }
+    }
+}
+
+impl LevelAssets {
+    /// Why the level can't be played yet, or `None` if it is open.
+    pub fn lock_reason(&self, index: usize, cleared_levels: &ClearedLevels) -> Option<LockReason> {
+        let pack = self
+            .packs
+            .iter()
+            .find(|pack| pack.levels.contains(&index))?;
+
+        match &pack.unlock {
+            UnlockRule::Open => {}
+            UnlockRule::Stars(stars) => {
+                if cleared_levels.star_count() < *stars {
+                    return Some(LockReason::Stars(*stars));
+                }
+            }
+            UnlockRule::AfterPack(name) => {
+                let is_pack_cleared =
+                    self.packs
+                        .iter()
+                        .filter(|pack| &pack.name == name)
+                        .all(|pack| {
+                            pack.levels
+                                .clone()
+                                .all(|i| cleared_levels.0.contains_key(&i))
+                        });
+                if !is_pack_cleared {
+                    return Some(LockReason::Pack(name.clone()));
+                }
+            }
+        }
+
+        if pack.sequential
+            && index > pack.levels.start
+            && !cleared_levels.0.contains_key(&(index - 1))
+        {
+            return Some(LockReason::PreviousLevel(index - 1));
+        }
+
+        None
+    }
+}
+
+/// Loads `levels/manifest.ron` and every level it lists.
+#[derive(Default)]
+struct LevelManifestLoader;
+
+impl AssetLoader for LevelManifestLoader {
+    type Asset = LevelAssets;
+    type Settings = ();
+    type Error = anyhow::Error;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let manifest = ron::de::from_bytes::<LevelManifest>(&bytes)?;
+
+        for pack in &manifest.packs {
+            let UnlockRule::AfterPack(name) = &pack.unlock else {
+                continue;
+            };
+            if !manifest.packs.iter().any(|pack| &pack.name == name) {
+                anyhow::bail!("pack {:?} is unlocked by unknown pack {name:?}", pack.name);
+            }
+        }
+
+        let mut levels = Vec::new();
+        let mut packs = Vec::new();
+        for pack in manifest.packs {
+            let start = levels.len();
+            for path in &pack.levels {
+                // level paths are relative to the manifest
+                let path = load_context.asset_path().resolve_embed(path)?;
+                levels.push(load_context.load(path));
+            }
+            packs.push(LevelPackInfo {
+                name: pack.name,
+                unlock: pack.unlock,
+                sequential: pack.sequential,
+                levels: start..levels.len(),
+            });
         }
+
+        Ok(LevelAssets { levels, packs })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest.ron"]
     }
 }
 
@@ -229,9 +357,10 @@ fn spawn_level(
     current_level: Res<CurrentLevel>,
     level_layouts: Res<Assets<LevelLayout>>,
 ) {
-    let level_layout = level_layouts
-        .get(&current_level.layout)
-        .expect("Level layout not found");
+    let Some(level_layout) = level_layouts.get(&current_level.layout) else {
+        error!("Current level layout not found in assets");
+        return;
+    };
 
     commands
         .spawn((
@@ -241,7 +370,9 @@ fn spawn_level(
             Visibility::default(),
             StateScoped(Screen::Gameplay),
         ))
-        .with_children(|parent| spawn_grid(parent, bg_assets, item_assets, level_layout))
+        .with_children(|parent| {
+            spawn_grid(parent, bg_assets, item_assets, level_layout, GridMode::Play)
+        })
         .observe(reset_tint_colors_on_out);
 }
 
@@ -250,13 +381,14 @@ fn spawn_level_ui_components(
     ui_assets: Res<UiAssets>,
     level_layouts: Res<Assets<LevelLayout>>,
     cleared_levels: Res<Persistent<ClearedLevels>>,
+    cleared_custom_levels: Res<Persistent<ClearedCustomLevels>>,
     current_level: Res<CurrentLevel>,
 ) {
-    let level_layout = level_layouts
-        .get(&current_level.layout)
-        .expect("Level layout not found");
+    let Some(level_layout) = level_layouts.get(&current_level.layout) else {
+        return;
+    };
 
-    let game_result = cleared_levels.0.get(&current_level.level);
+    let game_result = best_result(&current_level, &cleared_levels, &cleared_custom_levels);
 
     let mut ui_base = commands.spawn((
         Name::new("Level UI"),
@@ -284,11 +416,17 @@ fn spawn_level_ui_components(
         StateScoped(Screen::Gameplay),
         Pickable::IGNORE,
         children![widget::header(
-            format!(
-                "Level {}: {}",
-                current_level.level + 1, // 1-indexed level display
-                level_layout.meta.name
-            ),
+            if current_level.is_playtest {
+                format!("Playtest: {}", level_layout.meta.name)
+            } else if current_level.custom.is_some() {
+                format!("Custom: {}", level_layout.meta.name)
+            } else {
+                format!(
+                    "Level {}: {}",
+                    current_level.level + 1, // 1-indexed level display
+                    level_layout.meta.name
+                )
+            },
             Handle::clone(&ui_assets.font)
         )],
     ));
@@ -396,23 +534,43 @@ fn mission_line(
 
 const CELL_SIZE_BASE: f32 = 32.0;
 
-fn spawn_grid(
+/// What a grid is spawned for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridMode {
+    /// The player places bombs on the empty cells.
+    Play,
+    /// The level editor paints any cell, void cells included. Its tiles get no observers,
+    /// the editor adds its own.
+    Design,
+}
+
+impl GridMode {
+    fn screen(self) -> Screen {
+        match self {
+            GridMode::Play => Screen::Gameplay,
+            GridMode::Design => Screen::LevelEditor,
+        }
+    }
+}
+
+pub fn spawn_grid(
     commands: &mut RelatedSpawnerCommands<'_, ChildOf>,
     bg_assets: Res<BgAssets>,
     item_assets: Res<ItemAssets>,
     level_layout: &LevelLayout,
+    mode: GridMode,
 ) {
     commands
         .spawn((
             Name::new("Grid"),
             Transform::from_xyz(0.0, 0.0, 0.5),
             Visibility::default(),
-            StateScoped(Screen::Gameplay),
+            StateScoped(mode.screen()),
         ))
         .with_children(move |parent| {
             (0..level_layout.board_size.0).for_each(|x| {
                 (0..level_layout.board_size.1).for_each(|y| {
-                    spawn_grid_cell(parent, level_layout, x, y, &bg_assets, &item_assets);
+                    spawn_grid_cell(parent, level_layout, x, y, &bg_assets, &item_assets, mode);
                 });
             });
         });
@@ -425,6 +583,7 @@ fn spawn_grid_cell(
     y: u8,
     bg_assets: &Res<BgAssets>,
     item_assets: &Res<ItemAssets>,
+    mode: GridMode,
 ) {
     let scale_factor = 2.0;
     let cell_size = CELL_SIZE_BASE * scale_factor;
@@ -433,6 +592,11 @@ fn spawn_grid_cell(
 
     let grid_coord = GridCoord { x, y };
 
+    // void cells are not part of the board, but the editor has to paint over them
+    if mode == GridMode::Play && level_layout.objects.get(&grid_coord) == Some(&Item::Null) {
+        return;
+    }
+
     let mut entity_builder = builder.spawn((
         Name::new(format!("Tile ({}, {})", x, y)),
         GridTile {
@@ -461,15 +625,14 @@ fn spawn_grid_cell(
         grid_coord,
         Transform::from_xyz(0.0, 0.0, 3.0),
         Sprite::from_color(Color::NONE, Vec2::splat(cell_size)),
-        StateScoped(Screen::Gameplay),
+        StateScoped(mode.screen()),
     ));
 
     if let Some(&item) = level_layout.objects.get(&grid_coord) {
-        // if there is an item at the coordinate, disable interactions and spawn the item
+        // spawn the item at the coordinate
         entity_builder.with_children(|parent| {
             let mut item_entity = parent.spawn((
                 item,
-                ItemState::None,
                 grid_coord,
                 Sprite::from_atlas_image(
                     item_assets.sprite_sheet.clone(),
@@ -479,17 +642,25 @@ fn spawn_grid_cell(
                     },
                 ),
                 Visibility::default(),
-                Transform::from_scale(Vec3::splat(2.0)).with_translation(Vec3::new(0.0, 0.0, 1.0)),
-                StateScoped(Screen::Gameplay),
+                Transform::from_scale(Vec3::splat(2.0))
+                    .with_translation(Vec3::new(0.0, 0.0, 1.0))
+                    .with_rotation(item_rotation(item)),
+                StateScoped(mode.screen()),
             ));
 
-            if level_layout.fire_coord == grid_coord {
+            if level_layout.fire_coords.contains(&grid_coord) {
                 // if the item is a fire, add a special component
-                item_entity.with_child(fire(level_layout.fire_coord, item_assets));
+                item_entity.with_child(fire(grid_coord, item_assets));
             }
         });
+    }
+
+    if mode == GridMode::Design {
+        return;
+    }
 
-        // gray out the tile sprite to indicate that interactions are disabled
+    if level_layout.objects.contains_key(&grid_coord) {
+        // the level's own objects can't be replaced, so gray out the tile sprite to indicate that interactions are disabled
         entity_builder
             .entry::<Sprite>()
             .and_modify(|mut sprite| sprite.color = CELL_COLOR_DISABLED);
@@ -498,10 +669,18 @@ fn spawn_grid_cell(
         });
     } else {
         // if there is no item at the coordinate, interactions are enabled
-        entity_builder.observe(recolor_cells).observe(
+        entity_builder
+            .observe(recolor_cells)
+            .observe(track_hovered_tile)
+            .observe(forget_hovered_tile)
+            .observe(rotate_by_dragging)
+            .observe(erase_directional_bomb_on_click);
+        entity_builder.observe(
             |out: Trigger<Pointer<Pressed>>,
              coord: Query<&GridCoord>,
+             item_query: Query<(&Item, &GridCoord)>,
              selected_item: Res<SelectedItem>,
+             mut rotation_drag: ResMut<RotationDrag>,
              mut commands: Commands| {
                 let entity = out.target();
                 let &coord = coord.get(entity).unwrap();
@@ -510,10 +689,24 @@ fn spawn_grid_cell(
                     let Some(item) = selected_item.0 else {
                         return;
                     };
-                    commands.trigger(CreateObject::new(entity, coord, item).with_sound());
+                    commands.trigger(
+                        CreateObject::new(entity, coord, item)
+                            .with_sound()
+                            .with_history(),
+                    );
                 } else if out.button == PointerButton::Secondary {
+                    // a directional bomb may be dragged around to turn it, and is erased on
+                    // release instead
+                    if directional_bomb_at(item_query, coord).is_some() {
+                        rotation_drag.start();
+                        return;
+                    }
                     // If the secondary button is pressed, remove the item at the coordinate
-                    commands.trigger(CreateObject::new(entity, coord, Item::Eraser).with_sound());
+                    commands.trigger(
+                        CreateObject::new(entity, coord, Item::Eraser)
+                            .with_sound()
+                            .with_history(),
+                    );
                 }
             },
         );
@@ -535,6 +728,8 @@ fn _recolor_on<E: Debug + Clone + Reflect>(
 fn recolor_cells(
     over: Trigger<Pointer<Over>>,
     selected_item: Res<SelectedItem>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelLayout>>,
     target_query: Query<(&GridCoord, &GridTile)>,
     mut tint_query: Query<(&mut Sprite, &GridCoord), With<GridTileTint>>,
 ) {
@@ -544,14 +739,13 @@ fn recolor_cells(
     let Some(item) = selected_item.0 else {
         return;
     };
+    let Some(level_layout) = level_assets.get(&current_level.layout) else {
+        return;
+    };
 
-    let affected_coords: Vec<GridCoord> = item
-        .impact_zone()
-        .iter()
-        .map(|(dx, dy)| GridCoord {
-            x: target_coord.x.wrapping_add(*dx as u8),
-            y: target_coord.y.wrapping_add(*dy as u8),
-        })
+    // the board applies the level's rules, such as void cells absorbing blasts
+    let affected_coords: Vec<GridCoord> = Board::new(level_layout, [])
+        .impact_area(*target_coord, item)
         .collect();
 
     tint_query.iter_mut().for_each(|(mut sprite, grid_coord)| {
@@ -586,13 +780,14 @@ const CELL_COLOR_DISABLED: Color = Color::Srgba(palettes::css::LIGHT_GRAY);
 const CELL_COLOR_HOVERED: Color = Color::Srgba(palettes::css::LIGHT_BLUE);
 const CELL_COLOR_AFFECTED: Color = Color::Srgba(palettes::css::LIGHT_YELLOW);
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
-pub enum ItemState {
-    #[default]
-    None,
-    Burned,
-}
-
-fn move_to_edit_phase(mut next_state: ResMut<NextState<GamePhase>>) {
-    next_state.set(GamePhase::Edit);
+fn move_to_edit_phase(
+    current_level: Res<CurrentLevel>,
+    mut next_state: ResMut<NextState<GamePhase>>,
+) {
+    // a playtest runs the level exactly as the editor laid it out
+    next_state.set(if current_level.is_playtest {
+        GamePhase::Run
+    } else {
+        GamePhase::Edit
+    });
 }
@@ -1,19 +1,22 @@
 use std::{
-    collections::{HashMap, HashSet},
-    path::Path,
+    collections::{HashMap, hash_map::Entry},
+    path::{Path, PathBuf},
 };
 
 use crate::{
     audio::{SEVolume, SoundEffectAssets, sound_effect, stop_music},
+    clipboard::SystemClipboard,
     gameplay::{
-        CurrentLevel, GamePhase, GridCoord, Item, ItemState, LevelAssets, LevelLayout,
-        init_level::{MissionsSection, missions_section},
+        CurrentLevel, GamePhase, LevelAssets, LevelLayout,
+        init_level::{MissionsSection, is_playtest, missions_section},
+        run::RunningState,
     },
     screens::Screen,
-    theme::{UiAssets, widget},
+    theme::{UiAssets, palette::TEXT, widget},
 };
-use bevy::prelude::*;
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
 use bevy_persistent::prelude::*;
+use bombombo_core::encode_share_code;
 use serde::{Deserialize, Serialize};
 
 pub(super) fn plugin(app: &mut App) {
@@ -25,7 +28,7 @@ pub(super) fn plugin(app: &mut App) {
         (
             (
                 compute_game_result,
-                record_cleared_levels,
+                record_cleared_levels.run_if(not(is_playtest)),
                 (init_result_state, update_mission_status),
             )
                 .chain(),
@@ -37,6 +40,27 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Resource, Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct ClearedLevels(pub HashMap<usize, GameResult>);
 
+impl ClearedLevels {
+    /// Stars earned over all levels, one per completed mission.
+    pub fn star_count(&self) -> u32 {
+        self.0
+            .values()
+            .map(|result| {
+                result
+                    .mission_status
+                    .iter()
+                    .filter(|&&status| status)
+                    .count() as u32
+            })
+            .sum()
+    }
+}
+
+/// The best results of the custom levels, keyed by the hash of their files so that a level
+/// keeps its progress when it is renamed and loses it when it is edited.
+#[derive(Resource, Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ClearedCustomLevels(pub HashMap<u64, GameResult>);
+
 #[derive(Resource, Reflect, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[reflect(Resource)]
 pub struct GameResult {
@@ -50,7 +74,7 @@ pub struct GameResult {
 fn compute_game_result(
     current_level: Res<CurrentLevel>,
     level_assets: Res<Assets<LevelLayout>>,
-    query: Query<(&Item, &ItemState, &GridCoord)>,
+    running_state: Res<RunningState>,
     mut result: ResMut<GameResult>,
 ) {
     // reset to default values
@@ -67,78 +91,21 @@ fn compute_game_result(
         return;
     };
 
-    // Check if:
-    // - All bombs are burned
-    // - All rocks are destroyed
-    // - All jewels are saved
-    // - All enemies are defeated
-    let is_cleared = query.iter().all(|(&item, &state, _)| match item {
-        Item::BombSmall
-        | Item::BombMedium
-        | Item::BombLarge
-        | Item::BombHorizontal
-        | Item::BombVertical => state == ItemState::Burned,
-        Item::Rock => state == ItemState::Burned,
-        Item::Jewel => state == ItemState::None,
-        Item::Enemy => state == ItemState::Burned,
-        _ => true, // Other items are not relevant for the result
-    });
+    let Some(outcome) = running_state.outcome() else {
+        warn!("No simulation found for the current run");
+        return;
+    };
 
-    let bombs_list = query
-        .iter()
-        .filter(|(item, _state, _)| {
-            matches!(
-                item,
-                Item::BombSmall
-                    | Item::BombMedium
-                    | Item::BombLarge
-                    | Item::BombHorizontal
-                    | Item::BombVertical
-            )
-        })
-        .map(|(&item, &state, &coord)| (item, state, coord))
-        .collect::<Vec<_>>();
+    let is_cleared = outcome.is_cleared;
 
     let used_bomb_count = if is_cleared {
-        let total_bomb_count = bombs_list.len() as u8;
-
-        let level_bomb_count = level_layout
-            .objects
-            .iter()
-            .filter(|(_coord, item)| {
-                matches!(
-                    item,
-                    Item::BombSmall
-                        | Item::BombMedium
-                        | Item::BombLarge
-                        | Item::BombHorizontal
-                        | Item::BombVertical
-                )
-            })
-            .count() as u8;
-
-        total_bomb_count - level_bomb_count
+        outcome.used_bomb_count
     } else {
         u8::MAX
     };
 
     let affected_cell_count = if is_cleared {
-        bombs_list
-            .iter()
-            .flat_map(|(item, _state, coord)| {
-                item.impact_zone()
-                    .iter()
-                    .map(move |&(dx, dy)| GridCoord {
-                        x: (coord.x as i8 + dx) as u8,
-                        y: (coord.y as i8 + dy) as u8,
-                    })
-                    .filter(|coord| {
-                        // Ensure the coordinate is within the level bounds
-                        coord.x < level_layout.board_size.0 && coord.y < level_layout.board_size.1
-                    })
-            })
-            .collect::<HashSet<GridCoord>>()
-            .len() as u8
+        outcome.affected_cell_count
     } else {
         u8::MAX
     };
@@ -162,6 +129,7 @@ fn record_cleared_levels(
     current_level: Res<CurrentLevel>,
     game_result: Res<GameResult>,
     mut cleared_levels: ResMut<Persistent<ClearedLevels>>,
+    mut cleared_custom_levels: ResMut<Persistent<ClearedCustomLevels>>,
 ) {
     assert_eq!(
         current_level.level, game_result.level,
@@ -169,58 +137,112 @@ fn record_cleared_levels(
     );
 
     if game_result.is_cleared {
-        cleared_levels
-            .update(|cleared_levels| {
-                let current_best =
-                    cleared_levels
-                        .0
-                        .entry(current_level.level)
-                        .or_insert_with(|| GameResult {
-                            level: current_level.level,
-                            is_cleared: false,
-                            used_bomb_count: u8::MAX,
-                            affected_cell_count: u8::MAX,
-                            mission_status: [false; 3],
-                        });
-
-                current_best.is_cleared |= game_result.is_cleared;
-                current_best.used_bomb_count = current_best
-                    .used_bomb_count
-                    .min(game_result.used_bomb_count);
-                current_best.affected_cell_count = current_best
-                    .affected_cell_count
-                    .min(game_result.affected_cell_count);
-
-                current_best
-                    .mission_status
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(i, status)| {
-                        *status |= game_result.mission_status[i];
-                    });
-            })
-            .ok();
+        let result = match current_level.custom {
+            Some(hash) => cleared_custom_levels.update(|cleared_custom_levels| {
+                keep_best_result(
+                    cleared_custom_levels.0.entry(hash),
+                    current_level.level,
+                    &game_result,
+                );
+            }),
+            None => cleared_levels.update(|cleared_levels| {
+                keep_best_result(
+                    cleared_levels.0.entry(current_level.level),
+                    current_level.level,
+                    &game_result,
+                );
+            }),
+        };
+        result.ok();
+    }
+}
+
+/// Merges `game_result` into the best result recorded for a level.
+fn keep_best_result<K>(entry: Entry<'_, K, GameResult>, level: usize, game_result: &GameResult) {
+    let current_best = entry.or_insert_with(|| GameResult {
+        level,
+        is_cleared: false,
+        used_bomb_count: u8::MAX,
+        affected_cell_count: u8::MAX,
+        mission_status: [false; 3],
+    });
+
+    current_best.is_cleared |= game_result.is_cleared;
+    current_best.used_bomb_count = current_best
+        .used_bomb_count
+        .min(game_result.used_bomb_count);
+    current_best.affected_cell_count = current_best
+        .affected_cell_count
+        .min(game_result.affected_cell_count);
+
+    current_best
+        .mission_status
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, status)| {
+            *status |= game_result.mission_status[i];
+        });
+}
+
+/// The best result recorded for the current level, `None` for playtests.
+pub fn best_result<'a>(
+    current_level: &CurrentLevel,
+    cleared_levels: &'a ClearedLevels,
+    cleared_custom_levels: &'a ClearedCustomLevels,
+) -> Option<&'a GameResult> {
+    if current_level.is_playtest {
+        return None;
+    }
+    match current_level.custom {
+        Some(hash) => cleared_custom_levels.0.get(&hash),
+        None => cleared_levels.0.get(&current_level.level),
     }
 }
 
 fn init_result_state(
     mut commands: Commands,
     result: Res<GameResult>,
+    current_level: Res<CurrentLevel>,
+    running_state: Res<RunningState>,
     ui_assets: Res<UiAssets>,
     se_assets: Option<Res<SoundEffectAssets>>,
     se_volume: Res<SEVolume>,
 ) {
     // create UI node for graying out the whole screen
 
-    let mut entity = commands.spawn((
-        widget::ui_root("Result Screen"),
-        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
-        StateScoped(GamePhase::Result),
-        GlobalZIndex(2),
-    ));
+    let entity = commands
+        .spawn((
+            widget::ui_root("Result Screen"),
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            StateScoped(GamePhase::Result),
+            GlobalZIndex(2),
+        ))
+        .id();
 
     if result.is_cleared {
-        entity.insert(children![
+        // credit each chain separately when the level has several fires
+        let chain_outcomes = running_state.chain_outcomes();
+        let chain_lines: Vec<_> = if chain_outcomes.len() > 1 {
+            chain_outcomes
+                .iter()
+                .enumerate()
+                .map(|(index, chain)| {
+                    widget::text(
+                        format!(
+                            "Chain {}: {} Bombs, {} Cells",
+                            index + 1,
+                            chain.exploded_bomb_count,
+                            chain.affected_cell_count
+                        ),
+                        Handle::clone(&ui_assets.font),
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        commands.entity(entity).insert(children![
             widget::header("Level Cleared!", Handle::clone(&ui_assets.font)),
             widget::text(
                 format!("Used Bombs: {}", result.used_bomb_count),
@@ -230,30 +252,139 @@ fn init_result_state(
                 format!("Affected Cells: {}", result.affected_cell_count),
                 Handle::clone(&ui_assets.font)
             ),
-            widget::text_button("Select Level", &ui_assets, go_level_select),
-            widget::text_button("Retry", &ui_assets, retry_level),
-            widget::text_button("Next Level", &ui_assets, next_level),
+            (
+                Name::new("Chain Results"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                Children::spawn(SpawnIter(chain_lines.into_iter())),
+            ),
         ]);
 
         if let Some(se_assets) = se_assets {
             commands.spawn(sound_effect(se_assets.clear.clone(), &se_volume));
         }
     } else {
-        entity.insert(children![
-            widget::header("Level Failed...", Handle::clone(&ui_assets.font)),
-            widget::text_button("Select Level", &ui_assets, go_level_select),
-            widget::text_button("Retry", &ui_assets, retry_level),
-        ]);
+        commands.entity(entity).insert(children![widget::header(
+            "Level Failed...",
+            Handle::clone(&ui_assets.font)
+        )]);
 
         if let Some(se_assets) = se_assets {
             commands.spawn(sound_effect(se_assets.failed.clone(), &se_volume));
         }
     }
+
+    // custom levels are played one by one from the level select screen
+    let has_next_level = result.is_cleared && current_level.custom.is_none();
+    commands.entity(entity).with_children(|parent| {
+        if current_level.is_playtest {
+            parent.spawn(widget::text_button(
+                "Back to Editor",
+                &ui_assets,
+                go_level_editor,
+            ));
+            parent.spawn(widget::text_button("Retry", &ui_assets, retry_level));
+            return;
+        }
+        parent.spawn(widget::text_button(
+            "Select Level",
+            &ui_assets,
+            go_level_select,
+        ));
+        parent.spawn(widget::text_button("Retry", &ui_assets, retry_level));
+        if has_next_level {
+            parent.spawn(widget::text_button("Next Level", &ui_assets, next_level));
+        }
+    });
+    commands.entity(entity).with_child((
+        Name::new("Share"),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(16.0),
+            max_width: Val::Px(960.0),
+            ..default()
+        },
+        children![
+            widget::button_medium("Share", &ui_assets, show_share_code),
+            widget::button_medium("Copy", &ui_assets, copy_share_code),
+            (
+                Name::new("Share Code"),
+                ShareCodeText,
+                Text::default(),
+                TextFont::from_font(Handle::clone(&ui_assets.font)).with_font_size(20.0),
+                TextColor(TEXT),
+            ),
+        ],
+    ));
+}
+
+/// The share code of the level and this run's placements, once the share or copy button is
+/// clicked.
+#[derive(Component, Debug)]
+struct ShareCodeText;
+
+fn show_share_code(
+    _: Trigger<Pointer<Click>>,
+    current_level: Res<CurrentLevel>,
+    level_layouts: Res<Assets<LevelLayout>>,
+    running_state: Res<RunningState>,
+    mut text: Single<&mut Text, With<ShareCodeText>>,
+) {
+    let Some(code) = share_code(&current_level, &level_layouts, &running_state) else {
+        return;
+    };
+    text.0 = group_share_code(&code);
+}
+
+fn copy_share_code(
+    _: Trigger<Pointer<Click>>,
+    current_level: Res<CurrentLevel>,
+    level_layouts: Res<Assets<LevelLayout>>,
+    running_state: Res<RunningState>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    mut text: Single<&mut Text, With<ShareCodeText>>,
+) {
+    let Some(code) = share_code(&current_level, &level_layouts, &running_state) else {
+        return;
+    };
+    text.0 = match clipboard.set_text(&code) {
+        Ok(()) => format!("{} (copied)", group_share_code(&code)),
+        Err(error) => format!("{}\nCan't copy: {error}", group_share_code(&code)),
+    };
+}
+
+fn share_code(
+    current_level: &CurrentLevel,
+    level_layouts: &Assets<LevelLayout>,
+    running_state: &RunningState,
+) -> Option<String> {
+    let Some(level_layout) = level_layouts.get(&current_level.layout) else {
+        warn!("Current level layout not found in assets");
+        return None;
+    };
+
+    let code = encode_share_code(level_layout, running_state.placements());
+    info!("Share code of {:?}: {code}", level_layout.meta.name);
+    Some(code)
+}
+
+/// Groups of eight characters wrap and read more easily, the spaces are ignored on import.
+fn group_share_code(code: &str) -> String {
+    code.as_bytes()
+        .chunks(8)
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 fn update_mission_status(
     mut commands: Commands,
     cleared_levels: Res<Persistent<ClearedLevels>>,
+    cleared_custom_levels: Res<Persistent<ClearedCustomLevels>>,
+    result: Res<GameResult>,
     ui_assets: Res<UiAssets>,
     current_level: Res<CurrentLevel>,
     level_layouts: Res<Assets<LevelLayout>>,
@@ -264,7 +395,12 @@ fn update_mission_status(
         return;
     };
 
-    let game_result = cleared_levels.0.get(&current_level.level);
+    // a playtest isn't saved, so it shows how this run went
+    let game_result = if current_level.is_playtest {
+        Some(&*result)
+    } else {
+        best_result(&current_level, &cleared_levels, &cleared_custom_levels)
+    };
     for (entity, ChildOf(parent)) in missions_section_query.iter() {
         commands.entity(entity).despawn();
 
@@ -284,13 +420,24 @@ fn go_level_select(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState
     next_screen.set(Screen::LevelSelect);
 }
 
+fn go_level_editor(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::LevelEditor);
+}
+
 fn next_level(
     _: Trigger<Pointer<Click>>,
     level_assets: Res<LevelAssets>,
+    cleared_levels: Res<Persistent<ClearedLevels>>,
     current_level: ResMut<CurrentLevel>,
     next_phase: ResMut<NextState<GamePhase>>,
-    next_screen: ResMut<NextState<Screen>>,
+    mut next_screen: ResMut<NextState<Screen>>,
 ) {
+    if let Some(reason) = level_assets.lock_reason(current_level.level + 1, &cleared_levels) {
+        info!("Next level is locked: {reason}");
+        next_screen.set(Screen::LevelSelect);
+        return;
+    }
+
     move_to_level(
         current_level.level + 1,
         level_assets,
@@ -310,6 +457,8 @@ pub fn move_to_level(
     if let Some(level_handle) = level_assets.levels.get(next_level) {
         current_level.level = next_level;
         current_level.layout = Handle::clone(level_handle);
+        current_level.is_playtest = false;
+        current_level.custom = None;
         next_screen.set(Screen::Gameplay);
         next_phase.set(GamePhase::Init);
     } else {
@@ -318,11 +467,16 @@ pub fn move_to_level(
     }
 }
 
-fn insert_persistent_resources(mut commands: Commands) {
-    let data_dir = dirs::data_dir()
+/// The directory of the game's saved data.
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
         .map(|native_state_dir| native_state_dir.join("bevy-persistent"))
         .unwrap_or(Path::new("local").join("data"))
-        .join("bombombo");
+        .join("bombombo")
+}
+
+fn insert_persistent_resources(mut commands: Commands) {
+    let data_dir = data_dir();
 
     commands.insert_resource(
         Persistent::<ClearedLevels>::builder()
@@ -332,5 +486,14 @@ fn insert_persistent_resources(mut commands: Commands) {
             .default(ClearedLevels::default())
             .build()
             .expect("failed to initialize cleared levels persistent resource"),
-    )
+    );
+    commands.insert_resource(
+        Persistent::<ClearedCustomLevels>::builder()
+            .name("cleared custom levels")
+            .format(StorageFormat::Ron)
+            .path(data_dir.join("cleared_custom_levels.ron"))
+            .default(ClearedCustomLevels::default())
+            .build()
+            .expect("failed to initialize cleared custom levels persistent resource"),
+    );
 }
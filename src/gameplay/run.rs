@@ -1,52 +1,86 @@
 use std::{collections::HashMap, fmt::Debug};
 
 use bevy::{color::palettes, prelude::*};
+use bombombo_core::{Board, ChainOutcome, Simulation, SimulationOutcome};
 
 use crate::{
     audio::{SEVolume, SoundEffectAssets, sound_effect},
     gameplay::{
-        CurrentLevel, GamePhase, GridCoord, Item, ItemState,
+        CurrentLevel, GamePhase, GridCoord, Item, LevelLayout,
         animation::AffectedTileAnimation,
         edit::{CurrentPlacement, Fire, SelectedItem, fire},
-        init_level::{GridTile, ItemAssets, LevelLayout, reset_tint_colors},
+        init_level::{GridTile, ItemAssets, is_playtest, reset_tint_colors},
     },
     theme::{
+        UiAssets,
         interaction::InteractionImagePalette,
+        palette::BUTTON_TEXT,
         widget::{ItemButton, RunButton},
     },
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<RunningState>()
-        .init_resource::<BurningStack>()
         .insert_resource(RunningTimer(Timer::from_seconds(1.2, TimerMode::Repeating)));
     app.add_systems(
         OnEnter(GamePhase::Run),
         (
             disable_buttons,
             reset_tint_colors,
-            (init_run_state, record_current_placement).chain(),
+            (
+                init_run_state,
+                record_current_placement.run_if(not(is_playtest)),
+            )
+                .chain(),
         ),
     )
     .add_systems(Update, tick_timer.run_if(in_state(GamePhase::Run)))
     .add_observer(tick_simulation);
 }
 
-#[derive(Resource, Debug, Clone, PartialEq, Default)]
+#[derive(Resource, Debug, Clone, Default)]
 pub struct RunningState {
-    object_map: HashMap<GridCoord, (Item, Entity)>,
-    tick: u32,
+    simulation: Option<Simulation>,
+    entities: HashMap<GridCoord, Entity>,
+}
+
+impl RunningState {
+    pub fn is_finished(&self) -> bool {
+        self.simulation.as_ref().is_none_or(Simulation::is_finished)
+    }
+
+    pub fn outcome(&self) -> Option<SimulationOutcome> {
+        self.simulation.as_ref().map(Simulation::outcome)
+    }
+
+    /// The bombs the player placed for this run.
+    pub fn placements(&self) -> &[(GridCoord, Item)] {
+        self.simulation
+            .as_ref()
+            .map_or(&[], |simulation| simulation.board().placements())
+    }
+
+    pub fn chain_outcomes(&self) -> Vec<ChainOutcome> {
+        self.simulation
+            .as_ref()
+            .map(Simulation::chain_outcomes)
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Resource, Debug, Clone, PartialEq)]
 pub struct RunningTimer(pub Timer);
 
-#[derive(Resource, Debug, Clone, PartialEq, Default)]
-pub struct BurningStack(Vec<(GridCoord, Item, Entity)>);
-
 #[derive(Event, Debug, Clone, Copy, PartialEq)]
 struct NextTick;
 
+/// The first of the two cracked stages following the hard rock in the item sprite sheet.
+const HARD_ROCK_CRACKED_SPRITE_INDEX: usize = 16;
+
+/// The number of ticks left on the fuse of a burning timed bomb.
+#[derive(Component, Debug)]
+struct FuseCountdown;
+
 #[derive(Event, Debug, Clone, Copy, PartialEq)]
 pub struct Explode {
     pub parent_entity: Entity,
@@ -56,33 +90,28 @@ pub struct Explode {
 fn init_run_state(
     mut timer: ResMut<RunningTimer>,
     mut running_state: ResMut<RunningState>,
-    mut burning_stack: ResMut<BurningStack>,
+    current_level: Res<CurrentLevel>,
+    level_assets: Res<Assets<LevelLayout>>,
     item_query: Query<(Entity, &Item, &GridCoord), Without<Fire>>,
-    fire_query: Query<(Entity, &GridCoord), With<Fire>>,
 ) {
     timer.0.reset();
 
-    running_state.object_map = item_query
+    let Some(level_layout) = level_assets.get(&current_level.layout) else {
+        warn!("Current level layout not found in assets");
+        return;
+    };
+
+    running_state.entities = item_query
         .iter()
-        .map(|(entity, &item, &coord)| (coord, (item, entity)))
+        .map(|(entity, _item, &coord)| (coord, entity))
         .collect();
-    running_state.tick = 0;
-
-    let (_fire_entity, fire_coord) = fire_query
-        .single()
-        .expect("Fire item not found in object map");
 
-    burning_stack.0 = running_state
-        .object_map
+    let placements = item_query
         .iter()
-        .filter_map(|(&coord, &(item, entity))| {
-            if coord == *fire_coord {
-                Some((coord, item, entity))
-            } else {
-                None
-            }
-        })
-        .collect();
+        .filter(|&(_entity, item, coord)| level_layout.objects.get(coord) != Some(item))
+        .map(|(_entity, &item, &coord)| (coord, item));
+
+    running_state.simulation = Some(Simulation::new(Board::new(level_layout, placements)));
 }
 
 fn disable_buttons(
@@ -107,22 +136,15 @@ fn disable_buttons(
 fn record_current_placement(
     running_state: Res<RunningState>,
     current_level: Res<CurrentLevel>,
-    level_assets: Res<Assets<LevelLayout>>,
     mut current_placement: ResMut<CurrentPlacement>,
 ) {
-    let Some(level_layout) = level_assets.get(&current_level.layout) else {
-        warn!("Current level layout not found in assets");
+    let Some(simulation) = running_state.simulation.as_ref() else {
         return;
     };
 
     *current_placement = CurrentPlacement::new(
         current_level.level,
-        running_state
-            .object_map
-            .iter()
-            .filter(|&(&coord, &(item, _entity))| level_layout.objects.get(&coord) != Some(&item))
-            .map(|(&coord, &(item, _entity))| (coord, item))
-            .collect(),
+        simulation.board().placements().to_vec(),
     );
 }
 
@@ -131,10 +153,10 @@ fn tick_timer(
     mut running_timer: ResMut<RunningTimer>,
     mut commands: Commands,
     mut next_state: ResMut<NextState<GamePhase>>,
-    burning_stack: Res<BurningStack>,
+    running_state: Res<RunningState>,
 ) {
     if running_timer.0.tick(time.delta()).just_finished() {
-        if burning_stack.0.is_empty() {
+        if running_state.is_finished() {
             next_state.set(GamePhase::Result);
         }
         commands.trigger(NextTick);
@@ -145,26 +167,29 @@ fn tick_simulation(
     _trigger: Trigger<NextTick>,
     mut commands: Commands,
     item_assets: Res<ItemAssets>,
+    ui_assets: Res<UiAssets>,
     mut running_state: ResMut<RunningState>,
-    mut burning_stack: ResMut<BurningStack>,
-    mut query: Query<&mut ItemState>,
-    mut tile_query: Query<(Entity, &GridCoord), With<GridTile>>,
+    tile_query: Query<(Entity, &GridCoord), With<GridTile>>,
+    countdown_query: Query<Entity, With<FuseCountdown>>,
     se_assets: Option<Res<SoundEffectAssets>>,
     se_volume: Res<SEVolume>,
 ) {
-    running_state.tick += 1;
-    let mut filtered_burning_stack: Vec<_> = std::mem::take(&mut burning_stack.0)
-        .into_iter()
-        .filter(|(coord, _item, _entity)| running_state.object_map.remove(coord).is_some())
-        .collect();
-    filtered_burning_stack.sort_by_key(|(_, _, entity)| *entity);
-    filtered_burning_stack.dedup_by_key(|(_, _, entity)| *entity);
+    let RunningState {
+        simulation: Some(simulation),
+        entities,
+    } = running_state.as_mut()
+    else {
+        return;
+    };
+    if simulation.is_finished() {
+        return;
+    }
 
-    let affected_area = compute_affected_area(&filtered_burning_stack);
+    let events = simulation.step();
 
     // set affected tile animation
-    for (tile_entity, coord) in &mut tile_query {
-        if affected_area.iter().any(|&(c, _)| c == *coord) {
+    for (tile_entity, coord) in &tile_query {
+        if events.affected.iter().any(|&(c, _)| c == *coord) {
             commands.entity(tile_entity).with_children(|parent| {
                 parent.spawn((
                     Name::new("Burning Tile Animation"),
@@ -176,35 +201,9 @@ fn tick_simulation(
         }
     }
 
-    let affected_objects: Vec<_> = affected_area
-        .iter()
-        .filter_map(|&(coord, _count)| {
-            running_state
-                .object_map
-                .get(&coord)
-                .map(|&(item, entity)| (coord, item, entity))
-        })
-        .collect();
-
-    let affected_items: Vec<_> = affected_objects
-        .iter()
-        .filter(|&&(_, item, _)| !item.is_bomb())
-        .cloned()
-        .collect();
-
-    let affected_bombs: Vec<_> = affected_objects
-        .into_iter()
-        .filter(|&(_, item, _)| item.is_bomb())
-        .collect();
-
-    // remove affected items from the object map
-    affected_items.iter().for_each(|(coord, _item, _entity)| {
-        running_state.object_map.remove(coord);
-    });
-
     // SE
     if let Some(se_assets) = se_assets.as_ref() {
-        if !affected_items.is_empty() {
+        if !events.destroyed.is_empty() {
             commands.spawn(sound_effect(
                 Handle::clone(&se_assets.explosion_1),
                 &se_volume,
@@ -213,22 +212,38 @@ fn tick_simulation(
     }
 
     // explode animation
-    filtered_burning_stack
+    events
+        .exploded
         .iter()
-        .filter(|&(_, item, _)| item.is_bomb())
-        .chain(affected_items.iter())
-        .for_each(|&(_coord, item, entity)| {
-            let mut item_state = query.get_mut(entity).expect("Entity not found in query");
-            *item_state = ItemState::Burned;
+        .chain(events.destroyed.iter())
+        .for_each(|&(coord, item)| {
+            let Some(&entity) = entities.get(&coord) else {
+                warn!("No entity found for {:?} at {:?}", item, coord);
+                return;
+            };
             commands.trigger(Explode {
                 item,
                 parent_entity: entity,
             });
         });
 
+    // flash the mirrors bending line blasts
+    for coord in &events.deflected {
+        let Some(&entity) = entities.get(coord) else {
+            warn!("No entity found for the mirror at {:?}", coord);
+            continue;
+        };
+        if let Some(&item) = simulation.board().objects().get(coord) {
+            commands.trigger(Explode {
+                item,
+                parent_entity: entity,
+            });
+        }
+    }
+
     // SE
     if let Some(se_assets) = se_assets {
-        if !filtered_burning_stack.is_empty() {
+        if !events.exploded.is_empty() {
             commands.spawn(sound_effect(
                 Handle::clone(&se_assets.explosion_2),
                 &se_volume,
@@ -236,34 +251,81 @@ fn tick_simulation(
         }
     }
 
-    // set fire animation for affected bombs
-    affected_bombs.iter().for_each(|&(coord, _item, entity)| {
+    // set fire animation for ignited bombs
+    events.ignited.iter().for_each(|&(coord, _item)| {
+        if let Some(&entity) = entities.get(&coord) {
+            commands
+                .entity(entity)
+                .with_child(fire(coord, &item_assets));
+        }
+    });
+
+    // crack hard rocks that are still standing, further on their last hit
+    for &(coord, hits_left) in &events.cracked {
+        let Some(&entity) = entities.get(&coord) else {
+            continue;
+        };
         commands
             .entity(entity)
-            .with_child(fire(coord, &item_assets));
-    });
+            .entry::<Sprite>()
+            .and_modify(move |mut sprite| {
+                if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                    atlas.index = if hits_left == 1 {
+                        HARD_ROCK_CRACKED_SPRITE_INDEX + 1
+                    } else {
+                        HARD_ROCK_CRACKED_SPRITE_INDEX
+                    };
+                }
+            });
+    }
+
+    // show the ticks left on the fuses of timed bombs
+    countdown_query
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+    for &(coord, ticks) in &events.fuses {
+        if let Some(&entity) = entities.get(&coord) {
+            commands
+                .entity(entity)
+                .with_child(fuse_countdown(ticks, &ui_assets));
+        }
+    }
+
+    // jewels reached by an enemy break before the enemy takes their cell
+    for &coord in &events.stolen {
+        let Some(entity) = entities.remove(&coord) else {
+            warn!("No entity found for the jewel at {:?}", coord);
+            continue;
+        };
+        commands.trigger(Explode {
+            item: Item::Jewel,
+            parent_entity: entity,
+        });
+    }
 
-    // preserve bombs in the burning stack
-    burning_stack.0 = affected_bombs;
+    // move enemies to their new tiles
+    for &(from, to) in &events.moved {
+        let Some(entity) = entities.remove(&from) else {
+            warn!("No entity found for the enemy at {:?}", from);
+            continue;
+        };
+        let Some((tile_entity, _)) = tile_query.iter().find(|&(_, &coord)| coord == to) else {
+            warn!("No grid tile found for coord: {:?}", to);
+            continue;
+        };
+        commands.entity(tile_entity).add_child(entity);
+        commands.entity(entity).insert(to);
+        entities.insert(to, entity);
+    }
 }
 
-fn compute_affected_area(burning_stack: &[(GridCoord, Item, Entity)]) -> Vec<(GridCoord, usize)> {
-    burning_stack
-        .iter()
-        .filter(|(_, item, _)| item.is_bomb())
-        .flat_map(|(coord, item, _)| {
-            item.impact_zone().iter().map(move |&(dx, dy)| {
-                let new_coord = GridCoord {
-                    x: coord.x.wrapping_add(dx as u8),
-                    y: coord.y.wrapping_add(dy as u8),
-                };
-                (new_coord, 1) // Count each affected area once
-            })
-        })
-        .fold(HashMap::new(), |mut acc, (coord, count)| {
-            *acc.entry(coord).or_insert(0) += count;
-            acc
-        })
-        .into_iter()
-        .collect::<Vec<_>>()
+fn fuse_countdown(ticks: u8, ui_assets: &UiAssets) -> impl Bundle {
+    (
+        Name::new("Fuse Countdown"),
+        FuseCountdown,
+        Text2d::new(ticks.to_string()),
+        TextFont::from_font(Handle::clone(&ui_assets.font)).with_font_size(12.0),
+        TextColor(BUTTON_TEXT),
+        Transform::from_xyz(-4.0, -4.0, 0.2),
+    )
 }
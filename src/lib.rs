@@ -7,6 +7,7 @@ use bevy::{asset::AssetMetaCheck, prelude::*};
 
 mod asset_tracking;
 mod audio;
+mod clipboard;
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod gameplay;
@@ -45,6 +46,7 @@ impl Plugin for AppPlugin {
         app.add_plugins((
             asset_tracking::plugin,
             audio::plugin,
+            clipboard::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
             gameplay::plugin,
@@ -0,0 +1,194 @@
+//! The menu reading a share code typed by the player (opened from the level select screen).
+
+use bevy::{
+    input::{
+        ButtonState,
+        common_conditions::input_just_pressed,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use bombombo_core::decode_share_code;
+
+use crate::{
+    clipboard::SystemClipboard,
+    gameplay::PlaySharedLevel,
+    menus::Menu,
+    theme::{UiAssets, palette::TEXT, widget},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShareCodeInput>();
+    app.add_systems(OnEnter(Menu::ImportCode), spawn_import_code_menu);
+    app.add_systems(
+        Update,
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+            type_share_code,
+            update_share_code_labels,
+        )
+            .run_if(in_state(Menu::ImportCode)),
+    );
+}
+
+/// The code typed so far, and why the last one couldn't be played.
+#[derive(Resource, Debug, Clone, Default)]
+struct ShareCodeInput {
+    code: String,
+    error: String,
+}
+
+impl ShareCodeInput {
+    /// Appends the letters and digits of `text`, skipping the spaces a copied code is grouped
+    /// with.
+    fn push_code(&mut self, text: &str) {
+        let typed = text
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_uppercase());
+        self.code.extend(typed);
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+enum ShareCodeLabel {
+    Code,
+    Error,
+}
+
+fn spawn_import_code_menu(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    mut input: ResMut<ShareCodeInput>,
+) {
+    *input = ShareCodeInput::default();
+
+    commands.spawn((
+        widget::ui_root("Import Code Menu"),
+        GlobalZIndex(2),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        StateScoped(Menu::ImportCode),
+        children![
+            widget::header("Import Code", Handle::clone(&ui_assets.font)),
+            widget::text(
+                "Type or paste a share code, then press Enter",
+                Handle::clone(&ui_assets.font)
+            ),
+            (
+                Name::new("Typed Code"),
+                ShareCodeLabel::Code,
+                Text::default(),
+                TextFont::from_font(Handle::clone(&ui_assets.font)).with_font_size(20.0),
+                TextColor(TEXT),
+                Node {
+                    max_width: Val::Px(960.0),
+                    ..default()
+                },
+            ),
+            (
+                widget::label("", Some(Handle::clone(&ui_assets.font))),
+                ShareCodeLabel::Error,
+            ),
+            widget::text_button("Paste", &ui_assets, paste_on_click),
+            widget::text_button("Play", &ui_assets, play_on_click),
+            widget::text_button("Back", &ui_assets, go_back_on_click),
+        ],
+    ));
+}
+
+fn type_share_code(
+    mut commands: Commands,
+    mut keyboard_input: EventReader<KeyboardInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    mut input: ResMut<ShareCodeInput>,
+) {
+    // Ctrl+V, or Cmd+V on macOS
+    let is_shortcut_held = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if is_shortcut_held && keys.just_pressed(KeyCode::KeyV) {
+        paste_share_code(&mut clipboard, &mut input);
+    }
+
+    for event in keyboard_input.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => play_share_code(&mut commands, &mut input),
+            Key::Backspace => {
+                input.code.pop();
+            }
+            // the letter of a shortcut isn't part of the code
+            _ if is_shortcut_held => {}
+            _ => {
+                if let Some(text) = &event.text {
+                    input.push_code(text);
+                }
+            }
+        }
+    }
+}
+
+fn paste_on_click(
+    _: Trigger<Pointer<Click>>,
+    mut clipboard: NonSendMut<SystemClipboard>,
+    mut input: ResMut<ShareCodeInput>,
+) {
+    paste_share_code(&mut clipboard, &mut input);
+}
+
+fn paste_share_code(clipboard: &mut SystemClipboard, input: &mut ShareCodeInput) {
+    match clipboard.get_text() {
+        Ok(text) => input.push_code(&text),
+        Err(error) => input.error = format!("Can't paste: {error}"),
+    }
+}
+
+fn play_on_click(
+    _: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut input: ResMut<ShareCodeInput>,
+) {
+    play_share_code(&mut commands, &mut input);
+}
+
+fn play_share_code(commands: &mut Commands, input: &mut ShareCodeInput) {
+    match decode_share_code(&input.code) {
+        Ok((level, placements)) => commands.trigger(PlaySharedLevel { level, placements }),
+        Err(error) => input.error = format!("Can't read the code: {error}"),
+    }
+}
+
+fn update_share_code_labels(
+    input: Res<ShareCodeInput>,
+    mut label_query: Query<(&mut Text, &ShareCodeLabel)>,
+) {
+    for (mut text, label) in &mut label_query {
+        text.0 = match label {
+            // in groups of eight like on the result screen, so that long codes wrap
+            ShareCodeLabel::Code => {
+                let groups: Vec<_> = input
+                    .code
+                    .as_bytes()
+                    .chunks(8)
+                    .map(String::from_utf8_lossy)
+                    .collect();
+                format!("{}_", groups.join(" "))
+            }
+            ShareCodeLabel::Error => input.error.clone(),
+        };
+    }
+}
+
+fn go_back_on_click(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::None);
+}
+
+fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::None);
+}
@@ -63,6 +63,7 @@ fn menu_buttons(ui_assets: &UiAssets) -> impl Bundle {
         #[cfg(not(target_family = "wasm"))]
         children![
             widget::text_button("Play", ui_assets, enter_loading_or_gameplay_screen),
+            widget::text_button("Level Editor", ui_assets, enter_level_editor_screen),
             widget::text_button("Settings", ui_assets, open_settings_menu),
             widget::text_button("Credits", ui_assets, open_credits_menu),
             widget::text_button("Exit", ui_assets, exit_app),
@@ -88,6 +89,20 @@ fn enter_loading_or_gameplay_screen(
     }
 }
 
+/// The editor needs the level assets, so it waits for the loading screen like the levels do.
+#[cfg(not(target_family = "wasm"))]
+fn enter_level_editor_screen(
+    _: Trigger<Pointer<Click>>,
+    resource_handles: Res<ResourceHandles>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if resource_handles.is_all_done() {
+        next_screen.set(Screen::LevelEditor);
+    } else {
+        next_screen.set(Screen::Loading);
+    }
+}
+
 fn open_settings_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
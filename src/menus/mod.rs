@@ -1,6 +1,7 @@
 //! The game's menus and transitions between them.
 
 mod credits;
+mod import_code;
 mod main;
 mod pause;
 mod settings;
@@ -12,6 +13,7 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins((
         credits::plugin,
+        import_code::plugin,
         main::plugin,
         settings::plugin,
         pause::plugin,
@@ -27,4 +29,5 @@ pub enum Menu {
     Credits,
     Settings,
     Pause,
+    ImportCode,
 }
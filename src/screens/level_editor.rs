@@ -0,0 +1,887 @@
+//! The level editor, where designers paint a level, playtest it and save it as a level file.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use bevy::{
+    ecs::system::IntoObserverSystem,
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+    ui::Val::*,
+};
+use bombombo_core::{
+    EnemyBehavior, Facing, InventorySlot, LevelMetaData, MirrorTilt, Topology, VoidBehavior,
+};
+
+use crate::{
+    gameplay::{
+        BgAssets, CurrentLevel, GamePhase, GridCoord, GridMode, GridTile, Item, ItemAssets,
+        LevelLayout, item_rotation, spawn_grid,
+    },
+    screens::Screen,
+    theme::{UiAssets, widget},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<EditedLevel>()
+        .init_resource::<Brush>()
+        .init_resource::<NameInput>()
+        .init_resource::<EditorStatus>()
+        .init_resource::<PendingOverwrite>();
+
+    app.add_systems(
+        OnEnter(Screen::LevelEditor),
+        (
+            spawn_level_editor_screen,
+            spawn_palette,
+            spawn_board,
+            reset_editor_state,
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            spawn_board.run_if(resource_changed::<EditedLevel>),
+            observe_tiles,
+            type_level_name.run_if(|name_input: Res<NameInput>| name_input.0),
+            rotate_brush_with_keyboard.run_if(|name_input: Res<NameInput>| !name_input.0),
+            (highlight_brush, turn_brush_buttons).run_if(resource_changed::<Brush>),
+            update_editor_labels,
+        )
+            .run_if(in_state(Screen::LevelEditor)),
+    );
+}
+
+/// The level being edited. It outlives the editor screen so that a playtest comes back to it.
+#[derive(Resource, Debug, Clone)]
+pub struct EditedLevel(pub LevelLayout);
+
+impl Default for EditedLevel {
+    fn default() -> Self {
+        Self(LevelLayout {
+            board_size: (6, 6),
+            objects: HashMap::new(),
+            fire_coords: Vec::new(),
+            inventory: None,
+            enemy_behavior: EnemyBehavior::Idle,
+            voids: VoidBehavior::PassBlasts,
+            fuse_ticks: LevelLayout::DEFAULT_FUSE_TICKS,
+            hard_rock_hits: LevelLayout::DEFAULT_HARD_ROCK_HITS,
+            topology: Topology::Bounded,
+            meta: LevelMetaData {
+                name: "Untitled".to_string(),
+                min_bombs: 0,
+                min_affected_cells: 0,
+            },
+        })
+    }
+}
+
+/// The item painted on the cells that are clicked.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+struct Brush(Option<Item>);
+
+impl Brush {
+    /// Whether the brush paints `item`. Directional bombs match whichever way they face.
+    fn paints(&self, item: &Item) -> bool {
+        self.0.is_some_and(|selected| {
+            selected == *item || (selected.facing().is_some() && selected.same_kind(item))
+        })
+    }
+}
+
+/// Whether the keyboard types into the level name.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+struct NameInput(bool);
+
+/// What the last playtest or save attempt ran into.
+#[derive(Resource, Debug, Clone, Default)]
+struct EditorStatus(String);
+
+/// The file the last save refused to replace. Saving again to the same file replaces it.
+#[derive(Resource, Debug, Clone, Default)]
+struct PendingOverwrite(Option<PathBuf>);
+
+/// The items the editor can paint, bombs first.
+const PALETTE: [Item; 16] = [
+    Item::BombSmall,
+    Item::BombMedium,
+    Item::BombLarge,
+    Item::BombHorizontal,
+    Item::BombVertical,
+    Item::BombTimed,
+    Item::BombCone(Facing::Up),
+    Item::Rock,
+    Item::HardRock,
+    Item::Jewel,
+    Item::Enemy,
+    Item::Wall,
+    Item::Mirror(MirrorTilt::Rising),
+    Item::Mirror(MirrorTilt::Falling),
+    Item::Null,
+    Item::Eraser,
+];
+
+const MIN_BOARD_SIDE: u8 = 3;
+const MAX_BOARD_SIDE: u8 = 8;
+const MAX_NAME_LENGTH: usize = 24;
+/// The most ticks a fuse burns and the most blasts a hard rock takes.
+const MAX_RULE_VALUE: u8 = 9;
+/// The most bombs of a type the inventory limits the player to, beyond which it is unlimited.
+const MAX_STOCK: u8 = 9;
+
+/// The board of the edited level.
+#[derive(Component, Debug)]
+struct EditorBoard;
+
+/// A text showing a value of the edited level.
+#[derive(Component, Debug, Clone, Copy)]
+enum EditorLabel {
+    Name,
+    Width,
+    Height,
+    MinBombs,
+    MinAffectedCells,
+    FuseTicks,
+    HardRockHits,
+    Enemies,
+    Voids,
+    Edges,
+    Stock,
+    Status,
+}
+
+fn reset_editor_state(
+    mut brush: ResMut<Brush>,
+    mut name_input: ResMut<NameInput>,
+    mut status: ResMut<EditorStatus>,
+    mut pending_overwrite: ResMut<PendingOverwrite>,
+) {
+    brush.0 = None;
+    name_input.0 = false;
+    status.0.clear();
+    pending_overwrite.0 = None;
+}
+
+fn spawn_level_editor_screen(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    let font = || Some(Handle::clone(&ui_assets.font));
+
+    commands.spawn((
+        Name::new("Level Editor Screen"),
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexStart,
+            left: Px(48.0),
+            top: Px(48.0),
+            row_gap: Px(16.0),
+            ..default()
+        },
+        StateScoped(Screen::LevelEditor),
+        children![
+            widget::header("Level Editor", Handle::clone(&ui_assets.font)),
+            (
+                Name::new("Level Name"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Px(16.0),
+                    ..default()
+                },
+                children![
+                    (widget::label("", font()), EditorLabel::Name),
+                    widget::button_medium("Rename", &ui_assets, start_typing_name),
+                ],
+            ),
+            stepper(
+                "Width",
+                EditorLabel::Width,
+                &ui_assets,
+                shrink_width,
+                grow_width
+            ),
+            stepper(
+                "Height",
+                EditorLabel::Height,
+                &ui_assets,
+                shrink_height,
+                grow_height
+            ),
+            stepper(
+                "Bombs",
+                EditorLabel::MinBombs,
+                &ui_assets,
+                lower_min_bombs,
+                raise_min_bombs
+            ),
+            stepper(
+                "Cells",
+                EditorLabel::MinAffectedCells,
+                &ui_assets,
+                lower_min_affected_cells,
+                raise_min_affected_cells
+            ),
+            widget::text(
+                "Left click paints, right click lights a bomb, Q/E turn",
+                Handle::clone(&ui_assets.font)
+            ),
+            (widget::label("", font()), EditorLabel::Status),
+            (
+                Name::new("Editor Buttons"),
+                Node {
+                    column_gap: Px(16.0),
+                    ..default()
+                },
+                children![
+                    widget::button_medium("Test", &ui_assets, playtest_level),
+                    widget::button_medium("Save", &ui_assets, save_level),
+                    widget::button_medium("Back", &ui_assets, go_back),
+                ],
+            ),
+        ],
+    ));
+}
+
+/// A row with a name, a value and buttons lowering and raising it.
+fn stepper<E1, B1, M1, E2, B2, M2>(
+    name: &'static str,
+    label: EditorLabel,
+    ui_assets: &UiAssets,
+    lower: impl IntoObserverSystem<E1, B1, M1>,
+    raise: impl IntoObserverSystem<E2, B2, M2>,
+) -> impl Bundle
+where
+    E1: Event,
+    B1: Bundle,
+    E2: Event,
+    B2: Bundle,
+{
+    (
+        Name::new(format!("{name} Stepper")),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(10.0),
+            ..default()
+        },
+        children![
+            (
+                widget::label(name, Some(Handle::clone(&ui_assets.font))),
+                Node {
+                    width: Px(120.0),
+                    ..default()
+                }
+            ),
+            widget::button_small("-", ui_assets, lower),
+            (
+                widget::label("", Some(Handle::clone(&ui_assets.font))),
+                label
+            ),
+            widget::button_small("+", ui_assets, raise),
+        ],
+    )
+}
+
+/// A row with a name, a value and a button switching to the next value.
+fn switch<E, B, M>(
+    name: &'static str,
+    label: EditorLabel,
+    ui_assets: &UiAssets,
+    next: impl IntoObserverSystem<E, B, M>,
+) -> impl Bundle
+where
+    E: Event,
+    B: Bundle,
+{
+    (
+        Name::new(format!("{name} Switch")),
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Px(10.0),
+            ..default()
+        },
+        children![
+            (
+                widget::label(name, Some(Handle::clone(&ui_assets.font))),
+                Node {
+                    width: Px(120.0),
+                    ..default()
+                }
+            ),
+            widget::button_small(">", ui_assets, next),
+            (
+                widget::label("", Some(Handle::clone(&ui_assets.font))),
+                label
+            ),
+        ],
+    )
+}
+
+/// Spawns the palette, with the rules of the level below it.
+fn spawn_palette(mut commands: Commands, item_assets: Res<ItemAssets>, ui_assets: Res<UiAssets>) {
+    commands
+        .spawn((
+            Name::new("Side Panel"),
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                row_gap: Px(12.0),
+                right: Px(48.0),
+                top: Px(48.0),
+                ..default()
+            },
+            StateScoped(Screen::LevelEditor),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Name::new("Palette"),
+                    Node {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::px(4, 80.0),
+                        row_gap: Px(12.0),
+                        column_gap: Px(12.0),
+                        margin: UiRect::bottom(Px(12.0)),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    for item in PALETTE {
+                        parent.spawn(widget::item_button(
+                            Handle::clone(&item_assets.sprite_sheet),
+                            &ui_assets,
+                            Handle::clone(&item_assets.texture_atlas_layout),
+                            item,
+                            select_brush,
+                        ));
+                    }
+                });
+            parent.spawn(stepper(
+                "Stock",
+                EditorLabel::Stock,
+                &ui_assets,
+                lower_stock,
+                raise_stock,
+            ));
+            parent.spawn(stepper(
+                "Fuse",
+                EditorLabel::FuseTicks,
+                &ui_assets,
+                shorten_fuse,
+                lengthen_fuse,
+            ));
+            parent.spawn(stepper(
+                "Rock hits",
+                EditorLabel::HardRockHits,
+                &ui_assets,
+                lower_hard_rock_hits,
+                raise_hard_rock_hits,
+            ));
+            parent.spawn(switch(
+                "Enemies",
+                EditorLabel::Enemies,
+                &ui_assets,
+                switch_enemy_behavior,
+            ));
+            parent.spawn(switch(
+                "Voids",
+                EditorLabel::Voids,
+                &ui_assets,
+                switch_voids,
+            ));
+            parent.spawn(switch(
+                "Edges",
+                EditorLabel::Edges,
+                &ui_assets,
+                switch_topology,
+            ));
+        });
+}
+
+fn spawn_board(
+    mut commands: Commands,
+    bg_assets: Res<BgAssets>,
+    item_assets: Res<ItemAssets>,
+    edited_level: Res<EditedLevel>,
+    board_query: Query<Entity, With<EditorBoard>>,
+) {
+    for entity in &board_query {
+        commands.entity(entity).despawn();
+    }
+
+    commands
+        .spawn((
+            Name::new("Editor Board"),
+            EditorBoard,
+            Transform::default(),
+            Visibility::default(),
+            StateScoped(Screen::LevelEditor),
+        ))
+        .with_children(|parent| {
+            spawn_grid(
+                parent,
+                bg_assets,
+                item_assets,
+                &edited_level.0,
+                GridMode::Design,
+            )
+        });
+}
+
+fn observe_tiles(mut commands: Commands, tile_query: Query<Entity, Added<GridTile>>) {
+    for tile in &tile_query {
+        commands.entity(tile).observe(paint_cell);
+    }
+}
+
+/// Paints the brush on the pressed cell, or lights the bomb there on a right click.
+fn paint_cell(
+    trigger: Trigger<Pointer<Pressed>>,
+    coord_query: Query<&GridCoord>,
+    brush: Res<Brush>,
+    mut edited_level: ResMut<EditedLevel>,
+) {
+    let Ok(&coord) = coord_query.get(trigger.target()) else {
+        return;
+    };
+    let level = &mut edited_level.0;
+
+    match trigger.button {
+        PointerButton::Primary => {
+            let Some(item) = brush.0 else {
+                return;
+            };
+            if item == Item::Eraser {
+                level.objects.remove(&coord);
+            } else {
+                level.objects.insert(coord, item);
+            }
+            // only bombs can be lit
+            if !item.is_bomb() {
+                level.fire_coords.retain(|&fire_coord| fire_coord != coord);
+            }
+        }
+        PointerButton::Secondary => {
+            if level.fire_coords.contains(&coord) {
+                level.fire_coords.retain(|&fire_coord| fire_coord != coord);
+            } else if level.objects.get(&coord).is_some_and(Item::is_bomb) {
+                level.fire_coords.push(coord);
+            }
+        }
+        PointerButton::Middle => {}
+    }
+}
+
+fn select_brush(
+    trigger: Trigger<Pointer<Click>>,
+    item_query: Query<&Item, With<widget::ItemButton>>,
+    mut brush: ResMut<Brush>,
+) {
+    let Ok(&item) = item_query.get(trigger.target()) else {
+        return;
+    };
+    brush.0 = if brush.paints(&item) {
+        None
+    } else {
+        Some(item)
+    };
+}
+
+fn highlight_brush(
+    brush: Res<Brush>,
+    query: Query<(&mut ImageNode, &Item), With<widget::ItemButton>>,
+) {
+    for (mut image_node, item) in query {
+        if let Some(texture_atlas) = image_node.texture_atlas.as_mut() {
+            texture_atlas.index = if brush.paints(item) { 0 } else { 1 };
+        }
+    }
+}
+
+/// Turns the button of a directional bomb the way the brush paints it.
+fn turn_brush_buttons(
+    brush: Res<Brush>,
+    mut button_query: Query<(&mut Item, &Children), With<widget::ItemButton>>,
+    mut image_query: Query<&mut Transform, With<ImageNode>>,
+) {
+    let Some(selected) = brush.0.filter(|selected| selected.facing().is_some()) else {
+        return;
+    };
+
+    for (mut item, children) in &mut button_query {
+        if !item.same_kind(&selected) {
+            continue;
+        }
+        *item = selected;
+        for &child in children {
+            if let Ok(mut transform) = image_query.get_mut(child) {
+                transform.rotation = item_rotation(selected);
+            }
+        }
+    }
+}
+
+fn rotate_brush_with_keyboard(button_input: Res<ButtonInput<KeyCode>>, mut brush: ResMut<Brush>) {
+    let Some(item) = brush.0.filter(|item| item.facing().is_some()) else {
+        return;
+    };
+    if button_input.just_pressed(KeyCode::KeyE) {
+        brush.0 = Some(item.rotated_clockwise());
+    } else if button_input.just_pressed(KeyCode::KeyQ) {
+        brush.0 = Some(item.rotated_counter_clockwise());
+    }
+}
+
+fn start_typing_name(_: Trigger<Pointer<Click>>, mut name_input: ResMut<NameInput>) {
+    name_input.0 = true;
+}
+
+/// Types into the level name until Enter or Escape is pressed.
+fn type_level_name(
+    mut keyboard_input: EventReader<KeyboardInput>,
+    mut name_input: ResMut<NameInput>,
+    mut edited_level: ResMut<EditedLevel>,
+) {
+    for event in keyboard_input.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        let name = &mut edited_level.0.meta.name;
+        match &event.logical_key {
+            Key::Enter | Key::Escape => name_input.0 = false,
+            Key::Backspace => {
+                name.pop();
+            }
+            _ => {
+                let Some(text) = &event.text else {
+                    continue;
+                };
+                for character in text.chars().filter(|character| !character.is_control()) {
+                    if name.chars().count() < MAX_NAME_LENGTH {
+                        name.push(character);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_editor_labels(
+    edited_level: Res<EditedLevel>,
+    name_input: Res<NameInput>,
+    brush: Res<Brush>,
+    status: Res<EditorStatus>,
+    mut label_query: Query<(&mut Text, &EditorLabel)>,
+) {
+    let level = &edited_level.0;
+    for (mut text, label) in &mut label_query {
+        text.0 = match label {
+            EditorLabel::Name if name_input.0 => format!("{}_", level.meta.name),
+            EditorLabel::Name => level.meta.name.clone(),
+            EditorLabel::Width => level.board_size.0.to_string(),
+            EditorLabel::Height => level.board_size.1.to_string(),
+            EditorLabel::MinBombs => level.meta.min_bombs.to_string(),
+            EditorLabel::MinAffectedCells => level.meta.min_affected_cells.to_string(),
+            EditorLabel::FuseTicks => level.fuse_ticks.to_string(),
+            EditorLabel::HardRockHits => level.hard_rock_hits.to_string(),
+            EditorLabel::Enemies => match level.enemy_behavior {
+                EnemyBehavior::Idle => "Idle".to_string(),
+                EnemyBehavior::ChaseJewel => "Chase jewels".to_string(),
+            },
+            EditorLabel::Voids => match level.voids {
+                VoidBehavior::PassBlasts => "Pass blasts".to_string(),
+                VoidBehavior::AbsorbBlasts => "Absorb blasts".to_string(),
+            },
+            EditorLabel::Edges => match level.topology {
+                Topology::Bounded => "Bounded".to_string(),
+                Topology::Torus => "Wrap around".to_string(),
+            },
+            EditorLabel::Stock => match brush.0.filter(Item::is_bomb) {
+                None => "Pick a bomb".to_string(),
+                Some(bomb) => match stock(level, bomb) {
+                    Stock::None => "None".to_string(),
+                    Stock::Limited(count) => count.to_string(),
+                    Stock::Unlimited => "Unlimited".to_string(),
+                },
+            },
+            EditorLabel::Status => status.0.clone(),
+        };
+    }
+}
+
+/// Resizes the board, dropping the objects and fires left outside of it.
+fn resize_board(level: &mut LevelLayout, width: u8, height: u8) {
+    let width = width.clamp(MIN_BOARD_SIDE, MAX_BOARD_SIDE);
+    let height = height.clamp(MIN_BOARD_SIDE, MAX_BOARD_SIDE);
+    level.board_size = (width, height);
+    level
+        .objects
+        .retain(|coord, _| coord.x < width && coord.y < height);
+    level
+        .fire_coords
+        .retain(|coord| coord.x < width && coord.y < height);
+}
+
+fn shrink_width(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let (width, height) = edited_level.0.board_size;
+    resize_board(&mut edited_level.0, width.saturating_sub(1), height);
+}
+
+fn grow_width(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let (width, height) = edited_level.0.board_size;
+    resize_board(&mut edited_level.0, width.saturating_add(1), height);
+}
+
+fn shrink_height(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let (width, height) = edited_level.0.board_size;
+    resize_board(&mut edited_level.0, width, height.saturating_sub(1));
+}
+
+fn grow_height(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let (width, height) = edited_level.0.board_size;
+    resize_board(&mut edited_level.0, width, height.saturating_add(1));
+}
+
+fn lower_min_bombs(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let meta = &mut edited_level.0.meta;
+    meta.min_bombs = meta.min_bombs.saturating_sub(1);
+}
+
+fn raise_min_bombs(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let meta = &mut edited_level.0.meta;
+    meta.min_bombs = meta.min_bombs.saturating_add(1);
+}
+
+fn lower_min_affected_cells(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let meta = &mut edited_level.0.meta;
+    meta.min_affected_cells = meta.min_affected_cells.saturating_sub(1);
+}
+
+fn raise_min_affected_cells(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let meta = &mut edited_level.0.meta;
+    meta.min_affected_cells = meta.min_affected_cells.saturating_add(1);
+}
+
+fn shorten_fuse(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.fuse_ticks = level.fuse_ticks.saturating_sub(1).max(1);
+}
+
+fn lengthen_fuse(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.fuse_ticks = (level.fuse_ticks + 1).min(MAX_RULE_VALUE);
+}
+
+fn lower_hard_rock_hits(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.hard_rock_hits = level.hard_rock_hits.saturating_sub(1).max(1);
+}
+
+fn raise_hard_rock_hits(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.hard_rock_hits = (level.hard_rock_hits + 1).min(MAX_RULE_VALUE);
+}
+
+fn switch_enemy_behavior(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.enemy_behavior = match level.enemy_behavior {
+        EnemyBehavior::Idle => EnemyBehavior::ChaseJewel,
+        EnemyBehavior::ChaseJewel => EnemyBehavior::Idle,
+    };
+}
+
+fn switch_voids(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.voids = match level.voids {
+        VoidBehavior::PassBlasts => VoidBehavior::AbsorbBlasts,
+        VoidBehavior::AbsorbBlasts => VoidBehavior::PassBlasts,
+    };
+}
+
+fn switch_topology(_: Trigger<Pointer<Click>>, mut edited_level: ResMut<EditedLevel>) {
+    let level = &mut edited_level.0;
+    level.topology = match level.topology {
+        Topology::Bounded => Topology::Torus,
+        Topology::Torus => Topology::Bounded,
+    };
+}
+
+/// How many bombs of one type the player gets, in the order the stock stepper goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stock {
+    None,
+    Limited(u8),
+    Unlimited,
+}
+
+fn stock(level: &LevelLayout, bomb: Item) -> Stock {
+    match level
+        .inventory()
+        .iter()
+        .find(|slot| slot.item.same_kind(&bomb))
+    {
+        None => Stock::None,
+        Some(InventorySlot { count: None, .. }) => Stock::Unlimited,
+        Some(InventorySlot {
+            count: Some(count), ..
+        }) => Stock::Limited(*count),
+    }
+}
+
+/// Gives the player `stock` bombs of the type of `bomb`, writing out the inventory the level
+/// had by default the first time it is changed.
+fn set_stock(level: &mut LevelLayout, bomb: Item, stock: Stock) {
+    let mut inventory = level.inventory().to_vec();
+    let count = match stock {
+        Stock::None => {
+            inventory.retain(|slot| !slot.item.same_kind(&bomb));
+            None
+        }
+        Stock::Limited(count) => Some(Some(count)),
+        Stock::Unlimited => Some(None),
+    };
+    if let Some(count) = count {
+        match inventory.iter_mut().find(|slot| slot.item.same_kind(&bomb)) {
+            Some(slot) => slot.count = count,
+            None => inventory.push(InventorySlot { item: bomb, count }),
+        }
+    }
+
+    // levels keeping the default inventory leave it out of their file
+    level.inventory = (inventory != LevelLayout::DEFAULT_INVENTORY).then_some(inventory);
+}
+
+fn lower_stock(
+    _: Trigger<Pointer<Click>>,
+    brush: Res<Brush>,
+    mut edited_level: ResMut<EditedLevel>,
+) {
+    let Some(bomb) = brush.0.filter(Item::is_bomb) else {
+        return;
+    };
+    let lowered = match stock(&edited_level.0, bomb) {
+        Stock::None | Stock::Limited(1) => Stock::None,
+        Stock::Limited(count) => Stock::Limited(count.min(MAX_STOCK + 1) - 1),
+        Stock::Unlimited => Stock::Limited(MAX_STOCK),
+    };
+    set_stock(&mut edited_level.0, bomb, lowered);
+}
+
+fn raise_stock(
+    _: Trigger<Pointer<Click>>,
+    brush: Res<Brush>,
+    mut edited_level: ResMut<EditedLevel>,
+) {
+    let Some(bomb) = brush.0.filter(Item::is_bomb) else {
+        return;
+    };
+    let raised = match stock(&edited_level.0, bomb) {
+        Stock::None => Stock::Limited(1),
+        Stock::Limited(count) if count < MAX_STOCK => Stock::Limited(count + 1),
+        Stock::Limited(_) | Stock::Unlimited => Stock::Unlimited,
+    };
+    set_stock(&mut edited_level.0, bomb, raised);
+}
+
+/// Why the level can't be played yet, if anything.
+fn check_playable(level: &LevelLayout) -> Result<(), &'static str> {
+    if level.fire_coords.is_empty() {
+        return Err("Light a bomb with a right click first");
+    }
+    Ok(())
+}
+
+/// Runs the edited level in the gameplay screen, exactly as it is laid out.
+fn playtest_level(
+    _: Trigger<Pointer<Click>>,
+    edited_level: Res<EditedLevel>,
+    mut level_layouts: ResMut<Assets<LevelLayout>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut status: ResMut<EditorStatus>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if let Err(reason) = check_playable(&edited_level.0) {
+        status.0 = reason.to_string();
+        return;
+    }
+
+    current_level.layout = level_layouts.add(edited_level.0.clone());
+    current_level.is_playtest = true;
+    current_level.custom = None;
+    next_screen.set(Screen::Gameplay);
+    next_phase.set(GamePhase::Init);
+}
+
+fn save_level(
+    _: Trigger<Pointer<Click>>,
+    edited_level: Res<EditedLevel>,
+    mut status: ResMut<EditorStatus>,
+    mut pending_overwrite: ResMut<PendingOverwrite>,
+) {
+    status.0 = match write_level_file(&edited_level.0, &mut pending_overwrite.0) {
+        Ok(path) => format!("Saved {}", path.display()),
+        Err(error) => format!("Not saved: {error}"),
+    };
+}
+
+/// Writes the level to the custom levels folder, named after the level, and returns its path.
+/// The level shows up in the level select screen from there.
+///
+/// An existing file is only replaced when it is saved to twice in a row, the first attempt
+/// being remembered in `pending_overwrite`.
+#[cfg(not(target_family = "wasm"))]
+fn write_level_file(
+    level: &LevelLayout,
+    pending_overwrite: &mut Option<PathBuf>,
+) -> anyhow::Result<PathBuf> {
+    use crate::gameplay::custom_levels_dir;
+
+    check_playable(level).map_err(anyhow::Error::msg)?;
+
+    let dir = custom_levels_dir();
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.ron", file_stem(&level.meta.name)));
+    if path.exists() && pending_overwrite.as_ref() != Some(&path) {
+        let message = format!("{} exists, save again to replace it", path.display());
+        *pending_overwrite = Some(path);
+        anyhow::bail!(message);
+    }
+
+    *pending_overwrite = None;
+    std::fs::write(&path, level.to_ron()?)?;
+    Ok(path)
+}
+
+#[cfg(target_family = "wasm")]
+fn write_level_file(
+    _level: &LevelLayout,
+    _pending_overwrite: &mut Option<PathBuf>,
+) -> anyhow::Result<PathBuf> {
+    anyhow::bail!("the web build can't write files")
+}
+
+/// A file name for a level called `name`, e.g. `my_level` for "My Level!".
+#[cfg(not(target_family = "wasm"))]
+fn file_stem(name: &str) -> String {
+    let stem = name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let stem = stem.trim_matches('_');
+    if stem.is_empty() {
+        "untitled".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+fn go_back(_: Trigger<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
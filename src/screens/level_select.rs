@@ -1,10 +1,21 @@
-use bevy::{ecs::spawn::SpawnWith, input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{
+    ecs::spawn::SpawnWith,
+    input::{
+        common_conditions::input_just_pressed,
+        mouse::{MouseScrollUnit, MouseWheel},
+    },
+    prelude::*,
+};
 use bevy_persistent::Persistent;
 
 use crate::{
     Pause,
     audio::{MusicAssets, SpawnMusic},
-    gameplay::{ClearedLevels, CurrentLevel, GamePhase, GameResult, LevelAssets, move_to_level},
+    gameplay::{
+        ClearedCustomLevels, ClearedLevels, CurrentLevel, CustomLevels, GamePhase, GameResult,
+        LevelAssets, LockReason, custom_levels_dir, move_to_custom_level, move_to_level,
+        scan_custom_levels,
+    },
     menus::Menu,
     screens::Screen,
     theme::{UiAssets, widget},
@@ -13,7 +24,10 @@ use crate::{
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         OnEnter(Screen::LevelSelect),
-        (spawn_level_select_screen, spawn_music),
+        (
+            (scan_custom_levels, spawn_level_select_screen).chain(),
+            spawn_music,
+        ),
     );
 
     app.add_systems(
@@ -24,11 +38,14 @@ pub(super) fn plugin(app: &mut App) {
                     .and(in_state(Menu::None))
                     .and(input_just_pressed(KeyCode::KeyP).or(input_just_pressed(KeyCode::Escape))),
             ),
+            // P is typed into share codes
             close_menu.run_if(
                 in_state(Screen::LevelSelect)
                     .and(not(in_state(Menu::None)))
+                    .and(not(in_state(Menu::ImportCode)))
                     .and(input_just_pressed(KeyCode::KeyP)),
             ),
+            scroll_level_pack_list.run_if(in_state(Screen::LevelSelect).and(in_state(Menu::None))),
         ),
     );
     app.add_systems(OnExit(Screen::LevelSelect), (close_menu, unpause));
@@ -72,7 +89,9 @@ fn spawn_level_select_screen(
     mut commands: Commands,
     ui_assets: Res<UiAssets>,
     cleared_levels: Res<Persistent<ClearedLevels>>,
+    cleared_custom_levels: Res<Persistent<ClearedCustomLevels>>,
     level_assets: Res<LevelAssets>,
+    custom_levels: Res<CustomLevels>,
 ) {
     let mut entity = commands.spawn((
         widget::ui_root("Level Select Screen"),
@@ -80,7 +99,13 @@ fn spawn_level_select_screen(
         GlobalZIndex(0),
         children![
             widget::header("Select Level", Handle::clone(&ui_assets.font)),
-            stage_select_button_grid(&ui_assets, &cleared_levels, &level_assets)
+            level_pack_list(
+                &ui_assets,
+                &cleared_levels,
+                &level_assets,
+                &cleared_custom_levels,
+                &custom_levels
+            )
         ],
     ));
 
@@ -112,14 +137,26 @@ fn spawn_music(mut commands: Commands, music_assets: Res<MusicAssets>) {
 #[derive(Debug, Clone, Default)]
 pub struct LevelStatus {
     pub is_cleared: bool,
-    pub is_locked: bool,
+    pub lock_reason: Option<LockReason>,
     pub best_result: Option<GameResult>,
 }
 
-fn stage_select_button_grid(
+impl LevelStatus {
+    pub fn is_locked(&self) -> bool {
+        self.lock_reason.is_some()
+    }
+}
+
+/// The scrollable list of level packs.
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct LevelPackList;
+
+fn level_pack_list(
     ui_assets: &UiAssets,
     cleared_levels: &ClearedLevels,
     level_assets: &LevelAssets,
+    cleared_custom_levels: &ClearedCustomLevels,
+    custom_levels: &CustomLevels,
 ) -> impl Bundle {
     let ui_assets = ui_assets.clone();
     let level_status_list = level_assets
@@ -128,45 +165,165 @@ fn stage_select_button_grid(
         .enumerate()
         .map(|(index, _)| LevelStatus {
             is_cleared: cleared_levels.0.contains_key(&index),
-            // is_locked: (0..index).any(|i| !cleared_levels.0.contains_key(&i)) ,
-            is_locked: false, // TODO: REMOVE !!!
+            lock_reason: level_assets.lock_reason(index, cleared_levels),
             best_result: cleared_levels.0.get(&index).cloned(),
         })
         .collect::<Vec<_>>();
+    let packs = level_assets.packs.clone();
+    let custom_level_status_list = custom_levels
+        .0
+        .iter()
+        .map(|custom_level| {
+            let best_result = cleared_custom_levels.0.get(&custom_level.hash).cloned();
+            let status = LevelStatus {
+                is_cleared: best_result.is_some(),
+                lock_reason: None,
+                best_result,
+            };
+            (custom_level.name.clone(), status)
+        })
+        .collect::<Vec<_>>();
 
     (
+        Name::new("Level Packs"),
+        LevelPackList,
         Node {
-            display: Display::Grid,
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
             width: Val::Percent(60.0),
             height: Val::Percent(70.0),
-            grid_template_columns: vec![GridTrack::auto(); 4],
-            grid_template_rows: vec![GridTrack::auto(); 4],
-            justify_content: JustifyContent::SpaceBetween,
-            align_items: AlignItems::Stretch,
+            row_gap: Val::Px(16.0),
+            overflow: Overflow::scroll_y(),
             ..default()
         },
         Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
-            for (index, status) in level_status_list.into_iter().enumerate() {
-                let mut entity_bundle =
-                    parent.spawn(widget::level_button(index, &ui_assets, &status));
-                if !status.is_locked {
-                    entity_bundle.observe(
-                        move |_out: Trigger<Pointer<Click>>,
-                              level_assets: Res<LevelAssets>,
-                              current_level: ResMut<CurrentLevel>,
-                              next_phase: ResMut<NextState<GamePhase>>,
-                              next_screen: ResMut<NextState<Screen>>| {
-                            move_to_level(
-                                index,
-                                level_assets,
-                                current_level,
-                                next_phase,
-                                next_screen,
-                            );
-                        },
-                    );
-                }
+            for pack in packs {
+                parent.spawn(widget::text(pack.name, Handle::clone(&ui_assets.font)));
+
+                let ui_assets = ui_assets.clone();
+                let level_status_list = level_status_list[pack.levels.clone()].to_vec();
+                parent.spawn((
+                    Name::new("Level Pack"),
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        column_gap: Val::Px(16.0),
+                        row_gap: Val::Px(16.0),
+                        flex_shrink: 0.0,
+                        ..default()
+                    },
+                    Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                        for (index, status) in pack.levels.zip(level_status_list) {
+                            spawn_level_button(parent, index, &ui_assets, &status);
+                        }
+                    })),
+                ));
+            }
+
+            // custom levels follow the packs, always open
+            parent.spawn((
+                Name::new("Custom Levels Header"),
+                Node {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(16.0),
+                    flex_shrink: 0.0,
+                    ..default()
+                },
+                children![
+                    widget::text("Custom Levels", Handle::clone(&ui_assets.font)),
+                    widget::button_medium("Import", &ui_assets, open_import_code_menu),
+                ],
+            ));
+            if custom_level_status_list.is_empty() {
+                parent.spawn(widget::label(
+                    format!("Put level files in {}", custom_levels_dir().display()),
+                    Some(Handle::clone(&ui_assets.font)),
+                ));
+                return;
             }
+            parent.spawn((
+                Name::new("Custom Levels"),
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(16.0),
+                    row_gap: Val::Px(16.0),
+                    flex_shrink: 0.0,
+                    ..default()
+                },
+                Children::spawn(SpawnWith(move |parent: &mut ChildSpawner| {
+                    for (index, (name, status)) in custom_level_status_list.into_iter().enumerate()
+                    {
+                        spawn_custom_level_button(parent, index, name, &ui_assets, &status);
+                    }
+                })),
+            ));
         })),
     )
 }
+
+fn spawn_level_button(
+    parent: &mut ChildSpawner,
+    index: usize,
+    ui_assets: &UiAssets,
+    status: &LevelStatus,
+) {
+    let mut entity_bundle = parent.spawn(widget::level_button(index, ui_assets, status));
+    if !status.is_locked() {
+        entity_bundle.observe(
+            move |_out: Trigger<Pointer<Click>>,
+                  level_assets: Res<LevelAssets>,
+                  current_level: ResMut<CurrentLevel>,
+                  next_phase: ResMut<NextState<GamePhase>>,
+                  next_screen: ResMut<NextState<Screen>>| {
+                move_to_level(index, level_assets, current_level, next_phase, next_screen);
+            },
+        );
+    }
+}
+
+fn open_import_code_menu(_: Trigger<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
+    next_menu.set(Menu::ImportCode);
+}
+
+fn spawn_custom_level_button(
+    parent: &mut ChildSpawner,
+    index: usize,
+    name: String,
+    ui_assets: &UiAssets,
+    status: &LevelStatus,
+) {
+    parent
+        .spawn(widget::custom_level_button(name, ui_assets, status))
+        .observe(
+            move |_out: Trigger<Pointer<Click>>,
+                  custom_levels: Res<CustomLevels>,
+                  current_level: ResMut<CurrentLevel>,
+                  next_phase: ResMut<NextState<GamePhase>>,
+                  next_screen: ResMut<NextState<Screen>>| {
+                if let Some(custom_level) = custom_levels.0.get(index) {
+                    move_to_custom_level(custom_level, current_level, next_phase, next_screen);
+                }
+            },
+        );
+}
+
+/// Scrolls the level pack list with the mouse wheel.
+fn scroll_level_pack_list(
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut list_query: Query<&mut ScrollPosition, With<LevelPackList>>,
+) {
+    const LINE_HEIGHT: f32 = 48.0;
+
+    for event in mouse_wheel_events.read() {
+        let delta = match event.unit {
+            MouseScrollUnit::Line => event.y * LINE_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        };
+        for mut scroll_position in &mut list_query {
+            scroll_position.offset_y -= delta;
+        }
+    }
+}
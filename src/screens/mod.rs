@@ -1,6 +1,7 @@
 //! The game's main screen states and transitions between them.
 
 mod gameplay;
+mod level_editor;
 mod level_select;
 mod loading;
 mod splash;
@@ -14,6 +15,7 @@ pub(super) fn plugin(app: &mut App) {
 
     app.add_plugins((
         gameplay::plugin,
+        level_editor::plugin,
         loading::plugin,
         splash::plugin,
         level_select::plugin,
@@ -31,4 +33,5 @@ pub enum Screen {
     LevelSelect,
     Loading,
     Gameplay,
+    LevelEditor,
 }
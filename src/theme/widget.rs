@@ -10,7 +10,7 @@ use bevy::{
 };
 
 use crate::{
-    gameplay::Item,
+    gameplay::{Item, item_rotation},
     screens::LevelStatus,
     theme::{UiAssets, interaction::InteractionImagePalette, palette::*},
 };
@@ -106,6 +106,11 @@ pub fn text(text: impl Into<String>, font: Handle<Font>) -> impl Bundle {
 #[reflect(Component)]
 pub struct ItemButton;
 
+/// The text in the corner of an [`ItemButton`] showing how many of the item are left.
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct ItemCount;
+
 pub fn item_button<E, B, M, I>(
     image_handle: Handle<Image>,
     ui_assets: &UiAssets,
@@ -121,6 +126,7 @@ where
     let action = IntoObserverSystem::into_system(action);
     let texture_handle = Handle::clone(&ui_assets.ui_texture);
     let button_texture_layout = Handle::clone(&ui_assets.texture_atlas_layout);
+    let font_handle = Handle::clone(&ui_assets.font);
     (
         Name::new("Button"),
         Node::default(),
@@ -156,18 +162,36 @@ where
                         hovered: Color::Srgba(palettes::css::THISTLE),
                         pressed: Color::Srgba(palettes::css::PLUM.with_alpha(0.5)),
                     },
-                    children![(
-                        Name::new("Button Image"),
-                        ImageNode::from_atlas_image(
-                            image_handle,
-                            TextureAtlas {
-                                layout,
-                                index: item.to_sprite_index(),
+                    children![
+                        (
+                            Name::new("Button Image"),
+                            ImageNode::from_atlas_image(
+                                image_handle,
+                                TextureAtlas {
+                                    layout,
+                                    index: item.to_sprite_index(),
+                                },
+                            ),
+                            Transform::from_xyz(0.0, 0.0, 0.1)
+                                .with_scale(Vec2::splat(2.0).extend(1.0))
+                                .with_rotation(item_rotation(item)),
+                            Pickable::IGNORE,
+                        ),
+                        (
+                            Name::new("Item Count"),
+                            ItemCount,
+                            Text::default(),
+                            TextFont::from_font(font_handle).with_font_size(24.0),
+                            TextColor(BUTTON_TEXT),
+                            Node {
+                                position_type: PositionType::Absolute,
+                                right: Px(8.0),
+                                bottom: Px(4.0),
+                                ..default()
                             },
+                            Pickable::IGNORE,
                         ),
-                        Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec2::splat(2.0).extend(1.0)),
-                        Pickable::IGNORE,
-                    )],
+                    ],
                 ))
                 .observe(action);
         })),
@@ -175,7 +199,25 @@ where
 }
 
 pub fn level_button(index: usize, ui_assets: &UiAssets, level_status: &LevelStatus) -> impl Bundle {
-    let text = (index + 1).to_string();
+    level_status_button((index + 1).to_string(), 96.0, 48.0, ui_assets, level_status)
+}
+
+/// A level button wide enough for the name of a custom level, which has no number.
+pub fn custom_level_button(
+    name: impl Into<String>,
+    ui_assets: &UiAssets,
+    level_status: &LevelStatus,
+) -> impl Bundle {
+    level_status_button(name.into(), 288.0, 28.0, ui_assets, level_status)
+}
+
+fn level_status_button(
+    text: String,
+    width: f32,
+    font_size: f32,
+    ui_assets: &UiAssets,
+    level_status: &LevelStatus,
+) -> impl Bundle {
     let texture_handle = Handle::clone(&ui_assets.ui_texture);
     let layout = Handle::clone(&ui_assets.texture_atlas_layout);
     let font_handle = Handle::clone(&ui_assets.font);
@@ -188,7 +230,7 @@ pub fn level_button(index: usize, ui_assets: &UiAssets, level_status: &LevelStat
                 Name::new("Button Inner"),
                 Button,
                 Node {
-                    width: Px(96.0),
+                    width: Px(width),
                     height: Px(96.0),
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
@@ -208,70 +250,85 @@ pub fn level_button(index: usize, ui_assets: &UiAssets, level_status: &LevelStat
                     sides_scale_mode: SliceScaleMode::Stretch,
                     max_corner_scale: 4.0,
                 }))
-                .with_color(if level_status.is_locked {
+                .with_color(if level_status.is_locked() {
                     Color::Srgba(palettes::css::GRAY.with_alpha(0.5))
                 } else {
                     Color::Srgba(palettes::css::WHITE)
                 }),
-                children![
-                    (
-                        Name::new("Button Text"),
-                        Text(text),
-                        // TextFont::from_font_size(40.0),
-                        TextFont::from_font(font_handle).with_font_size(48.0),
-                        TextColor(if level_status.is_locked {
-                            BUTTON_TEXT_DISABLED
-                        } else {
-                            BUTTON_TEXT
-                        }),
-                        // Don't bubble picking events from the text up to the button.
-                        Pickable::IGNORE,
-                    ),
-                    (
-                        Name::new("Stars"),
-                        Node {
-                            position_type: PositionType::Absolute,
-                            display: Display::Flex,
-                            flex_direction: FlexDirection::Row,
-                            align_items: AlignItems::Center,
-                            justify_content: JustifyContent::Center,
-                            width: Percent(100.0),
-                            bottom: Px(8.0),
-                            column_gap: Px(-10.0),
-                            ..default()
-                        },
-                        Transform::default(),
-                        children![
-                            star(
-                                level_status
-                                    .best_result
-                                    .as_ref()
-                                    .is_some_and(|result| result.mission_status[0]),
-                                Handle::clone(&texture_handle),
-                                Handle::clone(&layout),
-                            ),
-                            star(
-                                level_status
-                                    .best_result
-                                    .as_ref()
-                                    .is_some_and(|result| result.mission_status[1]),
-                                Handle::clone(&texture_handle),
-                                Handle::clone(&layout),
-                            ),
-                            star(
-                                level_status
-                                    .best_result
-                                    .as_ref()
-                                    .is_some_and(|result| result.mission_status[2]),
-                                Handle::clone(&texture_handle),
-                                Handle::clone(&layout),
-                            ),
-                        ]
-                    )
-                ],
+                children![(
+                    Name::new("Button Text"),
+                    Text(text),
+                    // TextFont::from_font_size(40.0),
+                    TextFont::from_font(Handle::clone(&font_handle)).with_font_size(font_size),
+                    TextColor(if level_status.is_locked() {
+                        BUTTON_TEXT_DISABLED
+                    } else {
+                        BUTTON_TEXT
+                    }),
+                    // Don't bubble picking events from the text up to the button.
+                    Pickable::IGNORE,
+                )],
             ));
 
-            if !level_status.is_locked {
+            // Locked levels show what opens them where the stars would be.
+            if let Some(lock_reason) = &level_status.lock_reason {
+                entity_bundle.with_child((
+                    Name::new("Lock Reason"),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Px(8.0),
+                        ..default()
+                    },
+                    Text(lock_reason.to_string()),
+                    TextFont::from_font(font_handle).with_font_size(16.0),
+                    TextColor(BUTTON_TEXT_DISABLED),
+                    Pickable::IGNORE,
+                ));
+            } else {
+                entity_bundle.with_child((
+                    Name::new("Stars"),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        width: Percent(100.0),
+                        bottom: Px(8.0),
+                        column_gap: Px(-10.0),
+                        ..default()
+                    },
+                    Transform::default(),
+                    children![
+                        star(
+                            level_status
+                                .best_result
+                                .as_ref()
+                                .is_some_and(|result| result.mission_status[0]),
+                            Handle::clone(&texture_handle),
+                            Handle::clone(&layout),
+                        ),
+                        star(
+                            level_status
+                                .best_result
+                                .as_ref()
+                                .is_some_and(|result| result.mission_status[1]),
+                            Handle::clone(&texture_handle),
+                            Handle::clone(&layout),
+                        ),
+                        star(
+                            level_status
+                                .best_result
+                                .as_ref()
+                                .is_some_and(|result| result.mission_status[2]),
+                            Handle::clone(&texture_handle),
+                            Handle::clone(&layout),
+                        ),
+                    ],
+                ));
+            }
+
+            if !level_status.is_locked() {
                 entity_bundle.insert(InteractionImagePalette {
                     none: Color::Srgba(palettes::css::WHITE),
                     hovered: Color::Srgba(palettes::css::THISTLE),
@@ -522,3 +579,61 @@ where
         })),
     )
 }
+
+/// A button sized to sit next to the [`run_button`], with a short text.
+pub fn button_medium<E, B, M, I>(
+    text: impl Into<String>,
+    ui_assets: &UiAssets,
+    action: I,
+) -> impl Bundle
+where
+    E: Event,
+    B: Bundle,
+    I: IntoObserverSystem<E, B, M>,
+{
+    let text = text.into();
+    let texture_handle = Handle::clone(&ui_assets.ui_texture);
+    let layout = Handle::clone(&ui_assets.texture_atlas_layout);
+    let action = IntoObserverSystem::into_system(action);
+    let font_handle = Handle::clone(&ui_assets.font);
+    (
+        Name::new("Button"),
+        Node::default(),
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            parent
+                .spawn((
+                    Name::new("Button Inner"),
+                    Button,
+                    Node {
+                        width: Px(96.0),
+                        height: Px(48.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        padding: UiRect::top(Val::Px(2.0)),
+                        ..default()
+                    },
+                    ImageNode::from_atlas_image(texture_handle, TextureAtlas { layout, index: 1 })
+                        .with_mode(NodeImageMode::Sliced(TextureSlicer {
+                            border: BorderRect::all(12.0),
+                            center_scale_mode: SliceScaleMode::Stretch,
+                            sides_scale_mode: SliceScaleMode::Stretch,
+                            max_corner_scale: 4.0,
+                        })),
+                    InteractionImagePalette {
+                        none: Color::Srgba(palettes::css::WHITE),
+                        hovered: Color::Srgba(palettes::css::THISTLE),
+                        pressed: Color::Srgba(palettes::css::PLUM.with_alpha(0.5)),
+                    },
+                    children![(
+                        Name::new("Button Text"),
+                        Text(text),
+                        TextFont::from_font(font_handle).with_font_size(24.0),
+                        TextColor(BUTTON_TEXT),
+                        // Don't bubble picking events from the text up to the button.
+                        Pickable::IGNORE,
+                    )],
+                ))
+                .observe(action);
+        })),
+    )
+}